@@ -0,0 +1,372 @@
+//! Decides where enemies move and which animal they attack, see `enemies`.
+
+use macroquad::prelude::*;
+
+use crate::{physics, simulation::World};
+
+/// How often a chasing enemy reconsiders which animal is the best target
+const RETARGET_TIME: f32 = 0.5;
+
+/// Relative to the enemy's speed
+const PATROL_SPEED: f32 = 0.5;
+const PATROL_WAIT_TIME: (f32, f32) = (1., 4.);
+const ARRIVE_DISTANCE: f32 = 16.;
+
+/// How far ahead to look for buildings to steer around, in seconds of movement
+const AVOID_LOOKAHEAD: f32 = 0.5;
+/// Tried in order until one isn't blocked, in radians away from the intended direction
+const AVOID_ANGLES: [f32; 7] = [0., 0.5, -0.5, 1., -1., 1.5, -1.5];
+/// Shrinks the enemy when looking ahead, so that already touching a building isn't a collision
+const AVOID_RADIUS_FACTOR: f32 = 0.8;
+
+#[derive(Clone, Copy)]
+pub enum Targeting {
+    Nearest,
+    Weakest,
+    ClosestToDeathBall,
+}
+
+/// How an enemy behaves, selected per variant
+#[derive(Clone, Copy)]
+pub struct Profile {
+    pub targeting: Targeting,
+    /// Wanders within this distance of its post while idle, otherwise stands still
+    pub patrol_radius: Option<f32>,
+    /// Runs from the deathball once its health drops below this fraction
+    pub flee_below: Option<f32>,
+    /// Gives up chasing once this far from its post
+    pub leash: Option<f32>,
+}
+
+impl Profile {
+    /// Goes after whatever is nearest, however far it takes
+    pub const BRUTE: Profile = Profile {
+        targeting: Targeting::Nearest,
+        patrol_radius: Some(150.),
+        flee_below: None,
+        leash: None,
+    };
+
+    /// Goes after the animals closest to the deathball, to break it up
+    pub const HUNTER: Profile = Profile {
+        targeting: Targeting::ClosestToDeathBall,
+        patrol_radius: Some(250.),
+        flee_below: None,
+        leash: None,
+    };
+
+    /// Picks off the weakest animals, without straying far from its post
+    pub const GUARD: Profile = Profile {
+        targeting: Targeting::Weakest,
+        patrol_radius: Some(300.),
+        flee_below: None,
+        leash: Some(1000.),
+    };
+
+    /// Keeps close to its post and runs once it's been hurt
+    pub const COWARD: Profile = Profile {
+        targeting: Targeting::Nearest,
+        patrol_radius: Some(200.),
+        flee_below: Some(0.5),
+        leash: Some(800.),
+    };
+
+    /// Never leaves its post
+    pub const SENTRY: Profile = Profile {
+        targeting: Targeting::Nearest,
+        patrol_radius: None,
+        flee_below: None,
+        leash: Some(300.),
+    };
+}
+
+/// An animal that's close enough to be attacked
+pub struct Target {
+    pub handle: physics::Handle,
+    pub position: Vec2,
+    /// Lower is weaker
+    pub strength: f32,
+}
+
+/// What the enemy should do this step
+pub struct Decision {
+    pub velocity: Vec2,
+    /// Whatever is being chased or attacked
+    pub target: Option<Vec2>,
+    /// Only set once the enemy is in range to attack
+    pub attack: Option<Vec2>,
+}
+
+enum State {
+    Idle { timer: f32, waypoint: Option<Vec2> },
+    Chase { target: physics::Handle, timer: f32 },
+    Attack { target: physics::Handle },
+    Flee,
+    ReturnToPost,
+}
+
+pub struct Ai {
+    profile: Profile,
+    state: State,
+    /// Where the enemy started, and patrols around
+    post: Vec2,
+    radius: f32,
+    speed: f32,
+    attack_range: f32,
+}
+
+impl Ai {
+    pub fn new(profile: Profile, post: Vec2, radius: f32, speed: f32, attack_range: f32) -> Self {
+        Ai {
+            profile,
+            state: State::Idle {
+                timer: 0.,
+                waypoint: None,
+            },
+            post,
+            radius,
+            speed,
+            attack_range,
+        }
+    }
+
+    /// `health` is the fraction of health left, and `is_attacking` whether an attack has started
+    pub fn update(
+        &mut self,
+        world: &mut World,
+        position: Vec2,
+        health: f32,
+        targets: &[Target],
+        death_ball: Vec2,
+        is_attacking: bool,
+    ) -> Decision {
+        let find = |handle| targets.iter().find(|target| target.handle == handle);
+        let chase_best = || match self.pick_target(position, targets, death_ball) {
+            Some(target) => State::Chase { target, timer: 0. },
+            None => State::ReturnToPost,
+        };
+        let is_fleeing = matches!(self.profile.flee_below, Some(below) if health < below);
+        let distance_from_post = position.distance(self.post);
+        let is_past_leash = |fraction| match self.profile.leash {
+            Some(leash) => distance_from_post > leash * fraction,
+            None => false,
+        };
+
+        // transition between states
+        let next = match self.state {
+            _ if is_fleeing && !targets.is_empty() => State::Flee,
+            State::Flee => State::ReturnToPost,
+            State::Idle { .. } if !targets.is_empty() => chase_best(),
+            State::Chase { target, timer } => match find(target) {
+                None => chase_best(),
+                Some(_) if is_past_leash(1.) => State::ReturnToPost,
+                Some(found) if found.position.distance(position) <= self.attack_range => {
+                    State::Attack { target }
+                }
+                Some(_) if timer > RETARGET_TIME => chase_best(),
+                Some(_) => State::Chase {
+                    target,
+                    timer: timer + world.delta,
+                },
+            },
+            State::Attack { target } => match find(target) {
+                None if is_attacking => State::Attack { target },
+                None => chase_best(),
+                Some(found)
+                    if !is_attacking && found.position.distance(position) > self.attack_range =>
+                {
+                    State::Chase { target, timer: 0. }
+                }
+                Some(_) => State::Attack { target },
+            },
+            // only turn back once well within the leash, so that it doesn't bounce off of it
+            State::ReturnToPost if !targets.is_empty() && !is_past_leash(0.5) => chase_best(),
+            State::ReturnToPost if distance_from_post < ARRIVE_DISTANCE => State::Idle {
+                timer: 0.,
+                waypoint: None,
+            },
+            State::ReturnToPost => State::ReturnToPost,
+            State::Idle { timer, waypoint } => State::Idle { timer, waypoint },
+        };
+        self.state = next;
+
+        // act on the current state
+        let towards = |point: Vec2| (point - position).normalize_or_zero();
+        let (direction, speed, attack) = match self.state {
+            State::Idle {
+                ref mut timer,
+                ref mut waypoint,
+            } => match (*waypoint, self.profile.patrol_radius) {
+                (Some(point), _) if point.distance(position) < ARRIVE_DISTANCE => {
+                    *waypoint = None;
+                    *timer = world.rng.gen_range(PATROL_WAIT_TIME.0, PATROL_WAIT_TIME.1);
+                    (Vec2::ZERO, 0., None)
+                }
+                (Some(point), _) => (towards(point), self.speed * PATROL_SPEED, None),
+                (None, Some(radius)) if *timer <= 0. => {
+                    let angle = world.rng.gen_range(0., std::f32::consts::TAU);
+                    let distance = world.rng.gen_range(0., radius);
+                    *waypoint = Some(self.post + vec2(angle.cos(), angle.sin()) * distance);
+                    (Vec2::ZERO, 0., None)
+                }
+                (None, _) => {
+                    *timer -= world.delta;
+                    (Vec2::ZERO, 0., None)
+                }
+            },
+            State::Chase { target, .. } => {
+                let found = find(target).expect("chased targets are always nearby");
+                (towards(found.position), self.speed, None)
+            }
+            State::Attack { target } => match find(target) {
+                // hold still while winding up and lunging, the attack does the rest
+                _ if is_attacking => (Vec2::ZERO, 0., find(target).map(|t| t.position)),
+                Some(found) => (towards(found.position), self.speed, Some(found.position)),
+                None => (Vec2::ZERO, 0., None),
+            },
+            State::Flee => (-towards(death_ball), self.speed, None),
+            State::ReturnToPost => (towards(self.post), self.speed, None),
+        };
+
+        let target = match self.state {
+            State::Chase { target, .. } | State::Attack { target } => {
+                find(target).map(|target| target.position)
+            }
+            _ => None,
+        };
+
+        let direction = self.avoid_buildings(world, position, direction, speed);
+        Decision {
+            velocity: direction * speed,
+            target,
+            attack,
+        }
+    }
+
+    fn pick_target(
+        &self,
+        position: Vec2,
+        targets: &[Target],
+        death_ball: Vec2,
+    ) -> Option<physics::Handle> {
+        let score = |target: &Target| match self.profile.targeting {
+            Targeting::Nearest => target.position.distance(position),
+            Targeting::Weakest => target.strength,
+            Targeting::ClosestToDeathBall => target.position.distance(death_ball),
+        };
+        targets
+            .iter()
+            .min_by(|a, b| score(a).partial_cmp(&score(b)).unwrap())
+            .map(|target| target.handle)
+    }
+
+    /// Turns further and further away from the intended direction until it's clear of buildings
+    fn avoid_buildings(&self, world: &World, position: Vec2, direction: Vec2, speed: f32) -> Vec2 {
+        if direction == Vec2::ZERO {
+            return direction;
+        }
+        let radius = self.radius * AVOID_RADIUS_FACTOR;
+        let lookahead = speed * AVOID_LOOKAHEAD;
+        AVOID_ANGLES
+            .iter()
+            .map(|&angle| rotate(direction, angle))
+            .find(|&candidate| {
+                !world
+                    .physics
+                    .is_blocked(position, radius, candidate * lookahead)
+            })
+            // boxed in, so grinding against it is the best that can be done
+            .unwrap_or(direction)
+    }
+}
+
+fn rotate(vector: Vec2, angle: f32) -> Vec2 {
+    let (sin, cos) = angle.sin_cos();
+    vec2(
+        vector.x * cos - vector.y * sin,
+        vector.x * sin + vector.y * cos,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{entities::GenerationalIndex, rng::Rng, simulation::World};
+
+    fn target(world: &mut World, index: usize, position: Vec2, strength: f32) -> Target {
+        let idx = GenerationalIndex::single(index);
+        let handle = world.physics.add_dynamic(idx, physics::ball(8.), position);
+        Target {
+            handle: handle.into(),
+            position,
+            strength,
+        }
+    }
+
+    fn ai(profile: Profile) -> Ai {
+        Ai::new(profile, Vec2::ZERO, 16., 100., 50.)
+    }
+
+    #[test]
+    fn should_chase_then_attack_the_preferred_target() {
+        let mut world = World::new(Rng::new(0));
+        let near = target(&mut world, 0, vec2(200., 0.), 10.);
+        let weak = target(&mut world, 1, vec2(0., -400.), 1.);
+        let targets = [near, weak];
+
+        let mut brute = ai(Profile::BRUTE);
+        let decision = brute.update(&mut world, Vec2::ZERO, 1., &targets, Vec2::ZERO, false);
+        assert!(decision.velocity.x > 0.);
+        assert!(decision.attack.is_none());
+
+        let mut guard = ai(Profile::GUARD);
+        let decision = guard.update(&mut world, Vec2::ZERO, 1., &targets, Vec2::ZERO, false);
+        assert!(decision.velocity.y < 0.);
+
+        // chasing for a step, then in range to attack for the next
+        let position = vec2(180., 0.);
+        brute.update(&mut world, position, 1., &targets, Vec2::ZERO, false);
+        let decision = brute.update(&mut world, position, 1., &targets, Vec2::ZERO, false);
+        assert_eq!(decision.attack, Some(vec2(200., 0.)));
+    }
+
+    #[test]
+    fn should_flee_from_the_deathball_once_hurt() {
+        let mut world = World::new(Rng::new(0));
+        let targets = [target(&mut world, 0, vec2(100., 0.), 1.)];
+        let death_ball = vec2(200., 0.);
+
+        let mut coward = ai(Profile::COWARD);
+        let decision = coward.update(&mut world, Vec2::ZERO, 1., &targets, death_ball, false);
+        assert!(decision.velocity.x > 0.);
+        let decision = coward.update(&mut world, Vec2::ZERO, 0.25, &targets, death_ball, false);
+        assert!(decision.velocity.x < 0.);
+    }
+
+    #[test]
+    fn should_return_to_its_post_once_past_its_leash() {
+        let mut world = World::new(Rng::new(0));
+        let targets = [target(&mut world, 0, vec2(1000., 0.), 1.)];
+
+        let mut sentry = ai(Profile::SENTRY);
+        sentry.update(&mut world, vec2(200., 0.), 1., &targets, Vec2::ZERO, false);
+        let decision = sentry.update(&mut world, vec2(400., 0.), 1., &targets, Vec2::ZERO, false);
+        assert!(decision.velocity.x < 0.);
+    }
+
+    #[test]
+    fn should_steer_around_buildings() {
+        let mut world = World::new(Rng::new(0));
+        let idx = GenerationalIndex::single(0);
+        let wall = physics::cuboid(vec2(20., 400.));
+        world.physics.add_static(idx, wall, vec2(60., 0.));
+        world.physics.step(&mut Vec::new());
+
+        let targets = [target(&mut world, 1, vec2(500., 0.), 1.)];
+        let mut brute = ai(Profile::BRUTE);
+        let decision = brute.update(&mut world, Vec2::ZERO, 1., &targets, Vec2::ZERO, false);
+        assert!(decision.velocity.x > 0.);
+        assert!(decision.velocity.y.abs() > 0.);
+        assert!((decision.velocity.length() - 100.).abs() < 0.01);
+    }
+}
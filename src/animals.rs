@@ -1,20 +1,36 @@
 use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    death_ball::DeathBall, entities::GenerationalIndex, physics, spritesheet::Sprite, Resources,
+    death_ball::DeathBall, entities::GenerationalIndex, health::Health, physics, rng::Rng,
+    simulation::World, Resources,
 };
 
 const SPEED: f32 = 10.;
 const DAMPING: f32 = 0.1;
 
+const FADE_TIME: f32 = 1.;
+/// How hard an animal needs to be hit to knock it out of the death ball, per unit of mass
+const KNOCK_OUT_IMPULSE: f32 = 250.;
+
+const HEALTH_BAR_SIZE: (f32, f32) = (32., 8.);
+const HEALTH_BAR_OFFSET: (f32, f32) = (16., 28.);
+
+enum Status {
+    Alive { health: Health },
+    Dead { fade_timer: f32 },
+}
+
 pub struct Animal {
+    idx: GenerationalIndex,
     handle: physics::DynamicHandle,
-    sprite: Sprite,
+    variant: Variant,
+    status: Status,
     pub damage: u8,
     pub is_affected_by_death_ball: bool,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
 pub enum Variant {
     Cat,
     Dog,
@@ -34,69 +50,195 @@ pub enum Variant {
 struct VariantData {
     sprite: (f32, f32),
     damage: u8,
+    health: u16,
+    /// Multiplies how fast the death ball pulls the animal along
+    speed: f32,
+    /// Heavier animals are harder to knock out of the death ball
+    mass: f32,
+    ability: Option<Ability>,
+    rarity: Rarity,
+}
+
+/// How often buildings drop an animal when they don't have their own table, see `drops`
+#[derive(Clone, Copy)]
+pub enum Rarity {
+    Common,
+    Uncommon,
+    Rare,
+    /// Only dropped once per level
+    Legendary,
+}
+
+impl Rarity {
+    pub fn weight(self) -> u32 {
+        match self {
+            Rarity::Common => 10,
+            Rarity::Uncommon => 5,
+            Rarity::Rare => 2,
+            Rarity::Legendary => 1,
+        }
+    }
+
+    /// The most that buildings can drop in a level
+    pub fn cap(self) -> Option<u8> {
+        match self {
+            Rarity::Legendary => Some(1),
+            _ => None,
+        }
+    }
+}
+
+/// What happens to an enemy when an animal hits it, on top of the damage
+#[derive(Clone, Copy)]
+pub enum Ability {
+    /// Deals `damage` per second, for `duration` seconds
+    Poison { damage: f32, duration: f32 },
+    /// Pushes the enemy away at `speed` for a moment
+    Knockback { speed: f32 },
+    /// Multiplies the enemy's speed by `amount`, for `duration` seconds
+    Slow { amount: f32, duration: f32 },
 }
 
 impl Variant {
+    pub fn random(rng: &mut Rng) -> Self {
+        Animal::VARIANTS[rng.gen_range(0, Animal::VARIANTS.len())]
+    }
+
+    pub fn rarity(self) -> Rarity {
+        self.to_data().rarity
+    }
+
     fn to_data(self) -> VariantData {
         match self {
             Variant::Cat => VariantData {
                 sprite: (0., 1.),
                 damage: 3,
+                health: 20,
+                speed: 1.,
+                mass: 1.,
+                ability: None,
+                rarity: Rarity::Common,
             },
             Variant::Dog => VariantData {
                 sprite: (7., 0.),
                 damage: 3,
+                health: 24,
+                speed: 1.,
+                mass: 1.,
+                ability: None,
+                rarity: Rarity::Common,
             },
             Variant::Duck => VariantData {
                 sprite: (2., 0.),
                 damage: 2,
+                health: 16,
+                speed: 1.,
+                mass: 1.,
+                ability: None,
+                rarity: Rarity::Common,
             },
             Variant::Horse => VariantData {
                 sprite: (1., 0.),
                 damage: 1,
+                health: 16,
+                speed: 1.6,
+                mass: 1.,
+                ability: None,
+                rarity: Rarity::Common,
             },
             Variant::Kuma => VariantData {
                 sprite: (6., 0.),
                 damage: 4,
+                health: 40,
+                speed: 1.,
+                mass: 1.5,
+                ability: Some(Ability::Knockback { speed: 600. }),
+                rarity: Rarity::Rare,
             },
             Variant::Loaf => VariantData {
                 sprite: (4., 5.),
                 damage: 5,
+                health: 30,
+                speed: 1.,
+                mass: 1.,
+                ability: None,
+                rarity: Rarity::Rare,
             },
             Variant::Mouse => VariantData {
                 sprite: (4., 0.),
                 damage: 2,
+                health: 10,
+                speed: 1.,
+                mass: 1.,
+                ability: None,
+                rarity: Rarity::Common,
             },
             Variant::Poop => VariantData {
                 sprite: (5., 5.),
                 damage: 0,
+                health: 10,
+                speed: 1.,
+                mass: 1.,
+                ability: Some(Ability::Slow {
+                    amount: 0.4,
+                    duration: 5.,
+                }),
+                rarity: Rarity::Uncommon,
             },
             Variant::Rabbit => VariantData {
                 sprite: (5., 0.),
                 damage: 1,
+                health: 12,
+                speed: 1.,
+                mass: 1.,
+                ability: None,
+                rarity: Rarity::Common,
             },
             Variant::RubberDucky => VariantData {
                 sprite: (6., 5.),
                 damage: 50,
+                health: 20,
+                speed: 1.,
+                mass: 1.,
+                ability: None,
+                rarity: Rarity::Legendary,
             },
             Variant::Snail => VariantData {
                 sprite: (2., 1.),
                 damage: 1,
+                health: 60,
+                speed: 0.6,
+                mass: 4.,
+                ability: None,
+                rarity: Rarity::Uncommon,
             },
             Variant::Snake => VariantData {
                 sprite: (3., 0.),
                 damage: 3,
+                health: 20,
+                speed: 1.,
+                mass: 1.,
+                ability: Some(Ability::Poison {
+                    damage: 2.,
+                    duration: 4.,
+                }),
+                rarity: Rarity::Uncommon,
             },
             Variant::Turtle => VariantData {
                 sprite: (1., 1.),
                 damage: 2,
+                health: 50,
+                speed: 1.,
+                mass: 1.,
+                ability: None,
+                rarity: Rarity::Uncommon,
             },
         }
     }
 }
 
 impl Animal {
-    const VARIANTS: [Variant; 13] = [
+    pub const VARIANTS: [Variant; 13] = [
         Variant::Cat,
         Variant::Dog,
         Variant::Duck,
@@ -115,40 +257,155 @@ impl Animal {
     pub fn new(
         variant: Variant,
         idx: GenerationalIndex,
-        res: &mut Resources,
+        world: &mut World,
         position: Vec2,
     ) -> Self {
-        let variant = variant.to_data();
-        let sprite = res.assets.animals.sprite(variant.sprite.into());
         let collider = physics::ball(16.)
-            .mass(1.)
+            .mass(variant.to_data().mass)
             .linear_damping(DAMPING)
             .contact_events();
-        let handle = res.physics.add_dynamic(idx, collider, position);
+        let handle = world.physics.add_dynamic(idx, collider, position);
+        let data = variant.to_data();
         Animal {
-            sprite,
+            idx,
             handle,
-            damage: variant.damage,
+            variant,
+            status: Status::Alive {
+                health: Health::new(
+                    data.health,
+                    HEALTH_BAR_SIZE.into(),
+                    HEALTH_BAR_OFFSET.into(),
+                ),
+            },
+            damage: data.damage,
             is_affected_by_death_ball: false,
         }
     }
 
-    pub fn random(idx: GenerationalIndex, res: &mut Resources, position: Vec2) -> Self {
-        let variant = Animal::VARIANTS[rand::gen_range(0, Animal::VARIANTS.len())];
-        Animal::new(variant, idx, res, position)
+    pub fn idx(&self) -> GenerationalIndex {
+        self.idx
     }
 
-    pub fn update(&mut self, res: &mut Resources, death_ball: &DeathBall) {
-        if self.is_affected_by_death_ball {
-            let position = res.physics.get_position(self.handle);
-            let impulse = (death_ball.get_position(res) - position).normalize() * SPEED;
-            res.physics.apply_impulse(self.handle, impulse);
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    pub fn handle(&self) -> physics::Handle {
+        self.handle.into()
+    }
+
+    pub fn get_position(&self, world: &World) -> Vec2 {
+        world.physics.get_position(self.handle)
+    }
+
+    /// Also knocks the animal out of the death ball, if the impulse is strong enough for its mass
+    pub fn knock_back(&mut self, world: &mut World, impulse: Vec2) {
+        if impulse.length() >= KNOCK_OUT_IMPULSE * self.variant.to_data().mass {
+            self.is_affected_by_death_ball = false;
+        }
+        world.physics.apply_impulse(self.handle, impulse);
+    }
+
+    pub fn ability(&self) -> Option<Ability> {
+        self.variant.to_data().ability
+    }
+
+    /// Dead animals are left to fade away, and no longer interact with anything
+    pub fn is_alive(&self) -> bool {
+        matches!(self.status, Status::Alive { .. })
+    }
+
+    /// Returns whether or not the animal was killed
+    pub fn hurt(&mut self, damage: u8) -> bool {
+        if let Status::Alive { ref mut health } = self.status {
+            health.damage(damage.into());
+            if health.is_empty() {
+                self.status = Status::Dead {
+                    fade_timer: FADE_TIME,
+                };
+                self.is_affected_by_death_ball = false;
+                return true;
+            }
+        }
+        false
+    }
+
+    pub fn update(&mut self, world: &mut World, death_ball: &DeathBall) {
+        match self.status {
+            Status::Alive { ref mut health } => {
+                health.update(world.delta);
+
+                if self.is_affected_by_death_ball {
+                    let position = self.get_position(world);
+                    let data = self.variant.to_data();
+                    // scaled by mass so that heavier animals aren't also slower
+                    let impulse = (death_ball.get_position(world) - position).normalize()
+                        * SPEED
+                        * data.speed
+                        * data.mass;
+                    world.physics.apply_impulse(self.handle, impulse);
+                }
+            }
+            Status::Dead { ref mut fade_timer } => {
+                *fade_timer -= world.delta;
+                if *fade_timer < 0. {
+                    world.physics.remove(self.handle);
+                    world.deleted.push(self.idx);
+                }
+            }
+        }
+    }
+
+    pub fn draw(&self, res: &Resources, world: &World) {
+        let pos = self.get_position(world);
+        let rot = world.physics.get_rotation(self.handle);
+        let sprite = res
+            .assets
+            .animals
+            .sprite(self.variant.to_data().sprite.into());
+        match self.status {
+            Status::Alive { ref health } => {
+                sprite.draw(pos, rot);
+                health.draw(pos);
+            }
+            Status::Dead { fade_timer } => {
+                let alpha = fade_timer / FADE_TIME;
+                sprite.draw_alpha(pos, rot, alpha);
+            }
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::World;
+
+    #[test]
+    fn should_need_a_harder_hit_to_knock_out_heavier_animals() {
+        let mut world = World::new(Rng::new(0));
+        let mut horse = Animal::new(
+            Variant::Horse,
+            GenerationalIndex::single(0),
+            &mut world,
+            Vec2::ZERO,
+        );
+        let mut snail = Animal::new(
+            Variant::Snail,
+            GenerationalIndex::single(1),
+            &mut world,
+            vec2(100., 0.),
+        );
+        horse.is_affected_by_death_ball = true;
+        snail.is_affected_by_death_ball = true;
+
+        let impulse = vec2(KNOCK_OUT_IMPULSE, 0.);
+        horse.knock_back(&mut world, impulse);
+        snail.knock_back(&mut world, impulse);
+        assert!(!horse.is_affected_by_death_ball);
+        assert!(snail.is_affected_by_death_ball);
 
-    pub fn draw(&self, res: &Resources) {
-        let pos = res.physics.get_position(self.handle);
-        let rot = res.physics.get_rotation(self.handle);
-        self.sprite.draw(pos, rot);
+        snail.knock_back(&mut world, impulse * 4.);
+        assert!(!snail.is_affected_by_death_ball);
     }
 }
@@ -1,9 +1,13 @@
+use std::collections::HashMap;
+
 use macroquad::prelude::*;
 
 use crate::audio::Sound;
+use crate::levels::{Level, LevelDefinition};
 use crate::spritesheet::Spritesheet;
 
-const SPRITE_SIZE: f32 = 32.;
+pub const SPRITE_SIZE: f32 = 32.;
+pub const BUILDING_SPRITE_SIZE: f32 = SPRITE_SIZE * 4.;
 
 pub struct Assets {
     // textures
@@ -12,6 +16,9 @@ pub struct Assets {
     pub enemies: Spritesheet,
     pub props: Spritesheet,
 
+    // levels
+    pub levels: HashMap<Level, LevelDefinition>,
+
     // sfx
     pub smack: [Sound; 3],
     pub explode: [Sound; 2],
@@ -45,6 +52,9 @@ pub struct AssetsLoader {
     enemies: Option<Spritesheet>,
     props: Option<Spritesheet>,
 
+    // levels
+    levels: Option<HashMap<Level, LevelDefinition>>,
+
     // sfx
     smack: Option<[Sound; 3]>,
     explode: Option<[Sound; 2]>,
@@ -75,6 +85,7 @@ impl AssetsLoader {
             buildings: None,
             enemies: None,
             props: None,
+            levels: None,
             smack: None,
             explode: None,
             giant_horse_deathball: None,
@@ -110,11 +121,21 @@ impl AssetsLoader {
                 )
                 .await;
                 self.animals = Some(Spritesheet::new(animals.unwrap(), SPRITE_SIZE));
-                self.buildings = Some(Spritesheet::new(buildings.unwrap(), SPRITE_SIZE * 4.));
+                self.buildings = Some(Spritesheet::new(buildings.unwrap(), BUILDING_SPRITE_SIZE));
                 self.enemies = Some(Spritesheet::new(enemies.unwrap(), SPRITE_SIZE));
                 self.props = Some(Spritesheet::new(props.unwrap(), SPRITE_SIZE));
             }
             2 => {
+                let mut levels = HashMap::new();
+                for (level, path) in crate::levels::LEVEL_FILES {
+                    let source = load_string(path).await.unwrap();
+                    let definition = LevelDefinition::parse(&source)
+                        .unwrap_or_else(|err| panic!("Failed to parse {}: {}", path, err));
+                    levels.insert(level, definition);
+                }
+                self.levels = Some(levels);
+            }
+            3 => {
                 let (smack1, smack2, smack3, explode1, explode2) = join5(
                     load_file("./assets/sfx/smack1.ogg"),
                     load_file("./assets/sfx/smack2.ogg"),
@@ -130,45 +151,34 @@ impl AssetsLoader {
                 ]);
                 self.explode = Some([Sound::new(explode1.unwrap()), Sound::new(explode2.unwrap())]);
             }
-            3 => {
-                // skip loading bgm if disabled
-                if crate::debug::DISABLE_BGM {
-                    // we still need to load something
-                    let file = load_file("./assets/sfx/smack1.ogg").await.unwrap();
-                    self.giant_horse_deathball = Some(Sound::new(file.clone()));
-                    self.meadow_meadow = Some(Sound::new(file.clone()));
-                    self.send_it = Some(Sound::new(file.clone()));
-                    self.space = Some(Sound::new(file.clone()));
-                    self.take_me_home = Some(Sound::new(file));
-                    self.progress = 7;
-                } else {
-                    let file = load_file("./assets/music/giant-horse-deathball.ogg").await;
-                    self.giant_horse_deathball = Some(Sound::new(file.unwrap()));
-                }
-            }
             4 => {
+                let file = load_file("./assets/music/giant-horse-deathball.ogg").await;
+                self.giant_horse_deathball = Some(Sound::new(file.unwrap()));
+            }
+            5 => {
                 let file = load_file("./assets/music/meadow-meadow.ogg").await;
                 self.meadow_meadow = Some(Sound::new(file.unwrap()));
             }
-            5 => {
+            6 => {
                 let file = load_file("./assets/music/send-it.ogg").await;
                 self.send_it = Some(Sound::new(file.unwrap()));
             }
-            6 => {
+            7 => {
                 let file = load_file("./assets/music/space.ogg").await;
                 self.space = Some(Sound::new(file.unwrap()));
             }
-            7 => {
+            8 => {
                 let file =
                     load_file("./assets/music/take-me-home-country-roads-by-team-youwin.ogg").await;
                 self.take_me_home = Some(Sound::new(file.unwrap()));
             }
-            8 => {
+            9 => {
                 let assets = Assets {
                     animals: self.animals.take().unwrap(),
                     buildings: self.buildings.take().unwrap(),
                     enemies: self.enemies.take().unwrap(),
                     props: self.props.take().unwrap(),
+                    levels: self.levels.take().unwrap(),
                     smack: self.smack.take().unwrap(),
                     explode: self.explode.take().unwrap(),
                     giant_horse_deathball: self.giant_horse_deathball.take().unwrap(),
@@ -186,6 +196,6 @@ impl AssetsLoader {
         }
 
         self.progress += 1;
-        Progress::InProgress(self.progress as f32 / 8.)
+        Progress::InProgress(self.progress as f32 / 9.)
     }
 }
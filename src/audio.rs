@@ -10,9 +10,8 @@ use kira::{
 use macroquad::prelude::*;
 
 use super::Assets;
+use crate::{rng::Rng, save::Settings};
 
-const BGM_VOLUME: f64 = 0.5;
-const SFX_VOLUME: f64 = 0.5;
 const BGM_FADE_IN_TIME: f64 = 2.0;
 const BGM_FADE_OUT_TIME: f64 = 1.0;
 
@@ -27,12 +26,21 @@ pub struct AudioManager {
 }
 
 impl AudioManager {
-    pub fn new(assets: &mut Assets) -> Self {
+    pub fn new(assets: &mut Assets, settings: &Settings) -> Self {
         let mut manager = KiraAudioManager::new(Default::default()).unwrap();
-        let bgm = BackgroundMusic::new(&mut manager, assets);
-        let hit_sfx = SoundEffects::new(&mut manager, &mut assets.smack, MAX_HIT_SFX_PLAYING);
-        let killed_sfx =
-            SoundEffects::new(&mut manager, &mut assets.explode, MAX_KILLED_SFX_PLAYING);
+        let bgm = BackgroundMusic::new(&mut manager, assets, settings.bgm_volume);
+        let hit_sfx = SoundEffects::new(
+            &mut manager,
+            &mut assets.smack,
+            MAX_HIT_SFX_PLAYING,
+            settings.sfx_volume,
+        );
+        let killed_sfx = SoundEffects::new(
+            &mut manager,
+            &mut assets.explode,
+            MAX_KILLED_SFX_PLAYING,
+            settings.sfx_volume,
+        );
         Self {
             // keep ref to make sure manager isn't dropped
             _manager: manager,
@@ -41,6 +49,18 @@ impl AudioManager {
             killed_sfx,
         }
     }
+
+    /// Applies changed volumes, including to anything that's already playing
+    pub fn apply_settings(&mut self, settings: &Settings) {
+        self.bgm.set_volume(settings.bgm_volume);
+        self.hit_sfx.volume = settings.sfx_volume;
+        self.killed_sfx.volume = settings.sfx_volume;
+    }
+
+    pub fn set_sfx_enabled(&mut self, is_enabled: bool) {
+        self.hit_sfx.is_enabled = is_enabled;
+        self.killed_sfx.is_enabled = is_enabled;
+    }
 }
 
 // used by the assets module to decode and store sound data before it's needed by this module
@@ -59,7 +79,9 @@ impl Sound {
 }
 
 pub mod bgm {
-    #[derive(Clone, Copy)]
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
     pub enum Track {
         GiantHorseDeathball,
         MeadowMeadow,
@@ -68,6 +90,8 @@ pub mod bgm {
         TakeMeHome,
     }
 
+    pub const TRACKS: [Track; 5] = [GiantHorseDeathball, MeadowMeadow, SendIt, Space, TakeMeHome];
+
     pub use Track::*;
 }
 
@@ -78,10 +102,14 @@ pub struct BackgroundMusic {
     space: SoundHandle,
     take_me_home: SoundHandle,
     playing: Option<InstanceHandle>,
+    /// The last track asked for, even if it wasn't played, to start once re-enabled
+    track: Option<bgm::Track>,
+    is_enabled: bool,
+    volume: f64,
 }
 
 impl BackgroundMusic {
-    fn new(manager: &mut KiraAudioManager, assets: &mut Assets) -> Self {
+    fn new(manager: &mut KiraAudioManager, assets: &mut Assets, volume: f64) -> Self {
         Self {
             giant_horse_deathball: assets.giant_horse_deathball.add(manager),
             meadow_meadow: assets.meadow_meadow.add(manager),
@@ -89,6 +117,9 @@ impl BackgroundMusic {
             space: assets.space.add(manager),
             take_me_home: assets.take_me_home.add(manager),
             playing: None,
+            track: None,
+            is_enabled: true,
+            volume,
         }
     }
 
@@ -103,7 +134,8 @@ impl BackgroundMusic {
     }
 
     pub fn play(&mut self, track: bgm::Track) {
-        if crate::debug::DISABLE_BGM {
+        self.track = Some(track);
+        if !self.is_enabled {
             return;
         }
 
@@ -119,7 +151,7 @@ impl BackgroundMusic {
 
         self.playing = sound
             .play(InstanceSettings {
-                volume: BGM_VOLUME.into(),
+                volume: self.volume.into(),
                 fade_in_tween: Self::linear_tween(BGM_FADE_IN_TIME),
                 loop_start: InstanceLoopStart::Custom(0.),
                 ..Default::default()
@@ -127,6 +159,24 @@ impl BackgroundMusic {
             .ok();
     }
 
+    /// Restarts the last track asked for once re-enabled
+    pub fn set_enabled(&mut self, is_enabled: bool) {
+        self.is_enabled = is_enabled;
+        match self.track {
+            Some(track) if is_enabled => self.play(track),
+            _ => self.stop(),
+        }
+    }
+
+    fn set_volume(&mut self, volume: f64) {
+        self.volume = volume;
+        if let Some(instance) = &mut self.playing {
+            instance
+                .set_volume(volume)
+                .expect("Failed to set background music volume");
+        }
+    }
+
     fn linear_tween(time: f64) -> Option<Tween> {
         Some(Tween::linear(time))
     }
@@ -135,20 +185,29 @@ impl BackgroundMusic {
 pub struct SoundEffects {
     sounds: Vec<SoundHandle>,
     playing: Vec<InstanceHandle>,
+    is_enabled: bool,
+    volume: f64,
 }
 
 impl SoundEffects {
-    fn new(manager: &mut KiraAudioManager, sounds: &mut [Sound], max_playing: usize) -> Self {
+    fn new(
+        manager: &mut KiraAudioManager,
+        sounds: &mut [Sound],
+        max_playing: usize,
+        volume: f64,
+    ) -> Self {
         let sounds = sounds.iter_mut().map(|sound| sound.add(manager)).collect();
 
         Self {
             sounds,
             playing: Vec::with_capacity(max_playing),
+            is_enabled: true,
+            volume,
         }
     }
 
-    pub fn play(&mut self) {
-        if crate::debug::DISABLE_SFX {
+    pub fn play(&mut self, rng: &mut Rng) {
+        if !self.is_enabled {
             return;
         }
 
@@ -156,10 +215,10 @@ impl SoundEffects {
             .retain(|handle| handle.state() != InstanceState::Stopped);
 
         if self.playing.len() < self.playing.capacity() {
-            let idx = rand::gen_range(0, self.sounds.len());
+            let idx = rng.gen_range(0, self.sounds.len());
             let handle = self.sounds[idx]
                 .play(InstanceSettings {
-                    volume: SFX_VOLUME.into(),
+                    volume: self.volume.into(),
                     ..Default::default()
                 })
                 .unwrap();
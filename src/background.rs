@@ -1,6 +1,7 @@
 use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
 
-use crate::Resources;
+use crate::{assets::SPRITE_SIZE, Resources};
 
 pub struct Background {
     clear_color: Color,
@@ -20,6 +21,17 @@ impl Background {
         }
     }
 
+    /// Everything covered by props, in world coordinates, or None if there aren't any
+    pub fn bounds(&self) -> Option<Rect> {
+        let mut props = self.props.iter().map(|(position, prop)| {
+            let point = *position * SPRITE_SIZE + self.offset;
+            let size = prop.size * SPRITE_SIZE;
+            Rect::new(point.x, point.y, size.x, size.y)
+        });
+        let first = props.next()?;
+        Some(props.fold(first, |bounds, rect| bounds.combine_with(rect)))
+    }
+
     pub fn draw(&self, res: &Resources) {
         let tile_size = res.assets.props.cell_size;
 
@@ -34,7 +46,7 @@ impl Background {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
 pub enum Prop {
     Grass1,
     Grass2,
@@ -57,6 +69,22 @@ struct PropSprite {
 }
 
 impl Prop {
+    pub const VARIANTS: [Prop; 13] = [
+        Prop::Grass1,
+        Prop::Grass2,
+        Prop::Grass3,
+        Prop::FlowerWhite,
+        Prop::FlowerYellow,
+        Prop::FlowerRed,
+        Prop::FlowerBlack,
+        Prop::Gravel1,
+        Prop::Gravel2,
+        Prop::Gravel3,
+        Prop::Mud,
+        Prop::Hay,
+        Prop::Eggplant,
+    ];
+
     fn to_data(self) -> PropSprite {
         match self {
             Prop::Grass1 => PropSprite::sprite(0., 0.),
@@ -0,0 +1,298 @@
+//! Attack patterns for boss enemies, on top of their regular attack, see `enemies`.
+//!
+//! Bosses go through phases as their health drops, and each phase cycles through its patterns.
+
+use macroquad::prelude::*;
+
+use crate::{
+    enemies::Variant,
+    entities::GenerationalIndex,
+    groups, physics,
+    simulation::{Spawn, World},
+};
+
+const WIND_UP_FLASH: f32 = 0.2;
+const WIND_UP_DURATION: f32 = WIND_UP_FLASH * 4.;
+
+const SHOCKWAVE_DURATION: f32 = 0.6;
+/// Relative to the boss's radius
+const SHOCKWAVE_RANGE: f32 = 4.;
+const SHOCKWAVE_COLOR: Color = Color::new(0.6, 0.1, 0.6, 0.8);
+const SHOCKWAVE_THICKNESS: f32 = 24.;
+
+/// Relative to the boss's radius
+const SUMMON_DISTANCE: f32 = 1.5;
+
+const CHARGE_DURATION: f32 = 1.;
+
+pub struct Definition {
+    pub name: &'static str,
+    /// In order, the first phase is where the boss starts
+    pub phases: &'static [Phase],
+}
+
+pub struct Phase {
+    /// Starts once the boss's health drops below this fraction
+    pub below: f32,
+    pub patterns: &'static [Pattern],
+    /// Time between the end of a pattern and the start of the next one
+    pub cooldown: f32,
+}
+
+#[derive(Clone, Copy)]
+pub enum Pattern {
+    /// A ring that knocks back every animal it passes over
+    Shockwave,
+    Summon {
+        variant: Variant,
+        count: u8,
+    },
+    Charge {
+        speed: f32,
+    },
+}
+
+enum Status {
+    Cooldown {
+        timer: f32,
+    },
+    WindUp {
+        timer: f32,
+        pattern: Pattern,
+    },
+    Shockwave {
+        timer: f32,
+        handle: physics::SensorHandle,
+    },
+    Charge {
+        timer: f32,
+        velocity: Vec2,
+    },
+}
+
+/// Shown on the HUD for as long as the boss is alive
+pub struct BossStatus {
+    pub name: &'static str,
+    pub health: f32,
+    /// Starting from 1
+    pub phase: usize,
+}
+
+pub struct Boss {
+    definition: &'static Definition,
+    phase: usize,
+    next_pattern: usize,
+    status: Status,
+    idx: GenerationalIndex,
+    radius: f32,
+}
+
+impl Boss {
+    pub fn new(definition: &'static Definition, enemy_idx: GenerationalIndex, radius: f32) -> Self {
+        Boss {
+            definition,
+            phase: 0,
+            next_pattern: 0,
+            status: Status::Cooldown { timer: 0. },
+            // shares the group with regular attacks, so it knocks back animals the same way
+            idx: enemy_idx.with_group(groups::ENEMY_ATTACK),
+            radius,
+        }
+    }
+
+    pub fn status(&self, health: f32) -> BossStatus {
+        BossStatus {
+            name: self.definition.name,
+            health,
+            phase: self.phase + 1,
+        }
+    }
+
+    pub fn tint(&self) -> Option<Color> {
+        if let Status::WindUp { timer, .. } = self.status {
+            // flash purple repeatedly
+            let t = (timer % WIND_UP_FLASH) * (1. / WIND_UP_FLASH);
+            Some(Color::new(1., t, 1., 1.))
+        } else {
+            None
+        }
+    }
+
+    pub fn remove(&mut self, world: &mut World) {
+        if let Status::Shockwave { handle, .. } = self.status {
+            world.physics.remove(handle);
+        }
+        self.status = Status::Cooldown { timer: 0. };
+    }
+
+    /// `health` is the fraction of health left, and `target` what the boss is going after if
+    /// anything. Returns the velocity to move at instead of the usual, while in the middle of a
+    /// pattern.
+    pub fn update(
+        &mut self,
+        world: &mut World,
+        position: Vec2,
+        health: f32,
+        target: Option<Vec2>,
+    ) -> Option<Vec2> {
+        let phases = self.definition.phases;
+        while self.phase + 1 < phases.len() && health < phases[self.phase + 1].below {
+            self.phase += 1;
+            self.next_pattern = 0;
+        }
+        let phase = &phases[self.phase];
+
+        match self.status {
+            Status::Cooldown { ref mut timer } => {
+                *timer += world.delta;
+                // only start once there's something to attack
+                if *timer >= phase.cooldown && target.is_some() {
+                    let pattern = phase.patterns[self.next_pattern % phase.patterns.len()];
+                    self.next_pattern += 1;
+                    self.status = Status::WindUp { timer: 0., pattern };
+                }
+                None
+            }
+            Status::WindUp {
+                ref mut timer,
+                pattern,
+            } => {
+                *timer += world.delta;
+                if *timer < WIND_UP_DURATION {
+                    return Some(Vec2::ZERO);
+                }
+
+                self.status = match pattern {
+                    Pattern::Shockwave => {
+                        let collider = physics::ball(self.radius).intersection_events();
+                        let handle = world.physics.add_sensor(self.idx, collider, position);
+                        Status::Shockwave { timer: 0., handle }
+                    }
+                    Pattern::Summon { variant, count } => {
+                        let distance = self.radius * SUMMON_DISTANCE;
+                        for i in 0..count {
+                            let angle = std::f32::consts::TAU * i as f32 / count as f32;
+                            let offset = vec2(angle.cos(), angle.sin()) * distance;
+                            world.spawned.push(Spawn::Enemy(variant, position + offset));
+                        }
+                        Status::Cooldown { timer: 0. }
+                    }
+                    Pattern::Charge { speed } => {
+                        let direction = target
+                            .map_or(Vec2::ZERO, |target| (target - position).normalize_or_zero());
+                        Status::Charge {
+                            timer: 0.,
+                            velocity: direction * speed,
+                        }
+                    }
+                };
+                Some(Vec2::ZERO)
+            }
+            Status::Shockwave {
+                ref mut timer,
+                handle,
+            } => {
+                *timer += world.delta;
+                let timer = *timer;
+                if timer > SHOCKWAVE_DURATION {
+                    self.remove(world);
+                    return None;
+                }
+
+                let radius = self.shockwave_radius(timer);
+                world.physics.set_position(handle, position);
+                world.physics.set_ball_radius(handle, radius);
+                Some(Vec2::ZERO)
+            }
+            Status::Charge {
+                ref mut timer,
+                velocity,
+            } => {
+                *timer += world.delta;
+                if *timer > CHARGE_DURATION {
+                    self.status = Status::Cooldown { timer: 0. };
+                    return None;
+                }
+                Some(velocity)
+            }
+        }
+    }
+
+    fn shockwave_radius(&self, timer: f32) -> f32 {
+        let amount = timer / SHOCKWAVE_DURATION;
+        self.radius * (1. + (SHOCKWAVE_RANGE - 1.) * amount)
+    }
+
+    pub fn draw(&self, world: &World) {
+        if let Status::Shockwave { timer, handle } = self.status {
+            let position = world.physics.get_position(handle);
+            let radius = self.shockwave_radius(timer);
+            let (x, y) = position.into();
+            draw_circle_lines(x, y, radius, SHOCKWAVE_THICKNESS, SHOCKWAVE_COLOR);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{rng::Rng, simulation::World};
+
+    const TEST_BOSS: Definition = Definition {
+        name: "Test",
+        phases: &[
+            Phase {
+                below: 1.,
+                patterns: &[Pattern::Shockwave],
+                cooldown: 1.,
+            },
+            Phase {
+                below: 0.5,
+                patterns: &[Pattern::Summon {
+                    variant: Variant::Demon,
+                    count: 3,
+                }],
+                cooldown: 1.,
+            },
+        ],
+    };
+
+    /// Steps until a pattern has been started, returns the number of steps it took
+    fn wait_for_pattern(boss: &mut Boss, world: &mut World, health: f32) -> usize {
+        for steps in 0..1000 {
+            boss.update(world, Vec2::ZERO, health, Some(Vec2::X));
+            if !matches!(boss.status, Status::Cooldown { .. } | Status::WindUp { .. }) {
+                return steps;
+            }
+            if !world.spawned.is_empty() {
+                return steps;
+            }
+        }
+        panic!("no pattern was started");
+    }
+
+    #[test]
+    fn should_wait_for_a_target_before_starting_a_pattern() {
+        let mut world = World::new(Rng::new(0));
+        let mut boss = Boss::new(&TEST_BOSS, GenerationalIndex::single(0), 10.);
+        for _ in 0..1000 {
+            boss.update(&mut world, Vec2::ZERO, 1., None);
+        }
+        assert!(matches!(boss.status, Status::Cooldown { .. }));
+    }
+
+    #[test]
+    fn should_change_patterns_with_each_phase() {
+        let mut world = World::new(Rng::new(0));
+        let mut boss = Boss::new(&TEST_BOSS, GenerationalIndex::single(0), 10.);
+
+        wait_for_pattern(&mut boss, &mut world, 1.);
+        assert!(matches!(boss.status, Status::Shockwave { .. }));
+        assert_eq!(boss.status(1.).phase, 1);
+
+        boss.remove(&mut world);
+        wait_for_pattern(&mut boss, &mut world, 0.25);
+        assert_eq!(boss.status(0.25).phase, 2);
+        assert_eq!(world.spawned.len(), 3);
+    }
+}
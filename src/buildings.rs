@@ -1,39 +1,55 @@
 use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    animals::{Animal, Variant as AnimalVariant},
-    entities::{Entities, GenerationalIndex},
-    groups,
+    animals::Variant as AnimalVariant,
+    assets::BUILDING_SPRITE_SIZE,
+    entities::GenerationalIndex,
     health::Health,
     physics,
-    spritesheet::Sprite,
+    rng::Rng,
+    simulation::{Spawn, World},
     Resources,
 };
 
 const SPAWN_MAX_OFFSET: f32 = 20.;
 
-const FADE_TIME: f32 = 1.;
+/// The most a chunk of debris can be across
+const DEBRIS_SIZE: f32 = 48.;
+const DEBRIS_SPEED: (f32, f32) = (100., 300.);
+
+/// How far a lever reaches for gates to open
+const LEVER_RANGE: f32 = 1000.;
+const PULLED_LEVER_TINT: Color = Color::new(0.5, 0.5, 0.5, 1.);
+const GATE_TINT: Color = Color::new(1., 0.8, 0.5, 1.);
 
 const HEALTH_BAR_SIZE: (f32, f32) = (128., 16.);
 const HEALTH_BAR_OFFSET: (f32, f32) = (64., 92.);
 
 pub enum Status {
     Indestructible,
-    Destructible { health: Health },
-    Destroyed { fade_timer: f32 },
+    Destructible {
+        health: Health,
+    },
+    /// Breaks apart on the next update
+    Destroyed,
 }
 
 pub struct Building {
     idx: GenerationalIndex,
     handle: physics::StaticHandle,
-    sprite: Sprite,
+    variant: Variant,
+    position: Vec2,
     offset: Vec2,
     status: Status,
     spawn_count: u8,
     guaranteed_spawns: [Option<AnimalVariant>; 3],
+    is_pulled: bool,
+    /// For objectives that ask for specific buildings, see `LevelDefinition::tagged_buildings`
+    tag: Option<String>,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
 pub enum Variant {
     Barn,
     Car,
@@ -43,12 +59,15 @@ pub enum Variant {
     FeedingTrough,
     FenceH,
     FenceV,
+    GateH,
+    GateV,
     Garage,
     HayBaleH,
     HayBaleV,
     HorseCrossingSign,
     House1,
     House2,
+    Lever,
     OilBarrel,
     Outhouse,
     Portapotty,
@@ -66,9 +85,75 @@ struct VariantData {
     health: u8,
     spawn_count: u8,
     guaranteed_spawns: [Option<AnimalVariant>; 3],
+    /// What to pick the rest of the spawns from, with their weights, or empty to pick from every
+    /// animal by rarity
+    drops: &'static [(AnimalVariant, u32)],
+    behaviour: Behaviour,
+}
+
+const FARM_DROPS: &[(AnimalVariant, u32)] = &[
+    (AnimalVariant::Horse, 6),
+    (AnimalVariant::Cat, 3),
+    (AnimalVariant::Mouse, 3),
+    (AnimalVariant::Dog, 2),
+];
+const STABLE_DROPS: &[(AnimalVariant, u32)] = &[
+    (AnimalVariant::Horse, 8),
+    (AnimalVariant::Mouse, 1),
+    (AnimalVariant::Rabbit, 1),
+];
+const HAY_DROPS: &[(AnimalVariant, u32)] = &[
+    (AnimalVariant::Rabbit, 4),
+    (AnimalVariant::Mouse, 4),
+    (AnimalVariant::Snail, 2),
+    (AnimalVariant::Snake, 1),
+];
+const HOME_DROPS: &[(AnimalVariant, u32)] = &[
+    (AnimalVariant::Cat, 4),
+    (AnimalVariant::Dog, 4),
+    (AnimalVariant::Mouse, 2),
+    (AnimalVariant::Loaf, 1),
+    (AnimalVariant::RubberDucky, 1),
+];
+const TOILET_DROPS: &[(AnimalVariant, u32)] = &[
+    (AnimalVariant::Poop, 6),
+    (AnimalVariant::Mouse, 2),
+    (AnimalVariant::Snake, 1),
+];
+const GARAGE_DROPS: &[(AnimalVariant, u32)] = &[
+    (AnimalVariant::Snake, 3),
+    (AnimalVariant::Mouse, 3),
+    (AnimalVariant::Cat, 2),
+    (AnimalVariant::Turtle, 1),
+];
+
+/// What else a building does, besides getting in the way
+#[derive(Clone, Copy)]
+enum Behaviour {
+    None,
+    /// Once destroyed, knocks back everything in range and damages enemies and buildings
+    Explode {
+        radius: f32,
+        impulse: f32,
+        damage: u8,
+    },
+    /// Once destroyed, leaves a fire that damages animals and enemies every second
+    Burn {
+        radius: f32,
+        damage: u8,
+        duration: f32,
+    },
+    /// Indestructible, until a lever opens it
+    Gate,
+    /// Opens every gate in range the first time an animal hits it
+    Lever,
 }
 
 impl Variant {
+    pub fn drops(self) -> &'static [(AnimalVariant, u32)] {
+        self.to_data().drops
+    }
+
     fn to_data(self) -> VariantData {
         match self {
             Variant::Barn => VariantData {
@@ -78,6 +163,8 @@ impl Variant {
                 health: 200,
                 spawn_count: 3,
                 guaranteed_spawns: [Some(AnimalVariant::Horse), Some(AnimalVariant::Cat), None],
+                drops: FARM_DROPS,
+                behaviour: Behaviour::None,
             },
             Variant::Car => VariantData {
                 sprite: ((0., 4.), (2., 1.)),
@@ -86,6 +173,12 @@ impl Variant {
                 health: 150,
                 spawn_count: 1,
                 guaranteed_spawns: [Some(AnimalVariant::Cat), None, None],
+                drops: HOME_DROPS,
+                behaviour: Behaviour::Burn {
+                    radius: 120.,
+                    damage: 2,
+                    duration: 5.,
+                },
             },
             Variant::ConcreteWallH => VariantData {
                 sprite: ((5., 0.), (3., 1.)),
@@ -94,6 +187,8 @@ impl Variant {
                 health: 0,
                 spawn_count: 0,
                 guaranteed_spawns: [None, None, None],
+                drops: &[],
+                behaviour: Behaviour::None,
             },
             Variant::ConcreteWallV => VariantData {
                 sprite: ((7., 1.), (1., 3.)),
@@ -102,6 +197,8 @@ impl Variant {
                 health: 0,
                 spawn_count: 0,
                 guaranteed_spawns: [None, None, None],
+                drops: &[],
+                behaviour: Behaviour::None,
             },
             Variant::DownWithHorses => VariantData {
                 sprite: ((4., 1.), (2., 1.)),
@@ -110,6 +207,8 @@ impl Variant {
                 health: 100,
                 spawn_count: 1,
                 guaranteed_spawns: [Some(AnimalVariant::Horse), None, None],
+                drops: &[],
+                behaviour: Behaviour::None,
             },
             Variant::FeedingTrough => VariantData {
                 sprite: ((5., 2.), (2., 1.)),
@@ -118,6 +217,8 @@ impl Variant {
                 health: 25,
                 spawn_count: 2,
                 guaranteed_spawns: [Some(AnimalVariant::Horse), None, None],
+                drops: FARM_DROPS,
+                behaviour: Behaviour::None,
             },
             Variant::FenceH => VariantData {
                 sprite: ((2., 0.), (3., 1.)),
@@ -126,6 +227,8 @@ impl Variant {
                 health: 0,
                 spawn_count: 0,
                 guaranteed_spawns: [None, None, None],
+                drops: &[],
+                behaviour: Behaviour::None,
             },
             Variant::FenceV => VariantData {
                 sprite: ((0., 1.), (1., 3.)),
@@ -134,6 +237,28 @@ impl Variant {
                 health: 0,
                 spawn_count: 0,
                 guaranteed_spawns: [None, None, None],
+                drops: &[],
+                behaviour: Behaviour::None,
+            },
+            Variant::GateH => VariantData {
+                sprite: ((5., 0.), (3., 1.)),
+                size: (350., 40.),
+                offset: (0., -35.),
+                health: 0,
+                spawn_count: 0,
+                guaranteed_spawns: [None, None, None],
+                drops: &[],
+                behaviour: Behaviour::Gate,
+            },
+            Variant::GateV => VariantData {
+                sprite: ((7., 1.), (1., 3.)),
+                size: (54., 322.),
+                offset: (-4., -28.),
+                health: 0,
+                spawn_count: 0,
+                guaranteed_spawns: [None, None, None],
+                drops: &[],
+                behaviour: Behaviour::Gate,
             },
             Variant::Garage => VariantData {
                 sprite: ((6., 4.), (2., 1.)),
@@ -142,6 +267,8 @@ impl Variant {
                 health: 10,
                 spawn_count: 2,
                 guaranteed_spawns: [Some(AnimalVariant::Snake), None, None],
+                drops: GARAGE_DROPS,
+                behaviour: Behaviour::None,
             },
             Variant::HayBaleH => VariantData {
                 sprite: ((3., 2.), (1., 1.)),
@@ -150,6 +277,8 @@ impl Variant {
                 health: 10,
                 spawn_count: 1,
                 guaranteed_spawns: [Some(AnimalVariant::Rabbit), None, None],
+                drops: HAY_DROPS,
+                behaviour: Behaviour::None,
             },
             Variant::HayBaleV => VariantData {
                 sprite: ((4., 2.), (1., 1.)),
@@ -158,6 +287,8 @@ impl Variant {
                 health: 10,
                 spawn_count: 1,
                 guaranteed_spawns: [Some(AnimalVariant::Cat), None, None],
+                drops: HAY_DROPS,
+                behaviour: Behaviour::None,
             },
             Variant::HorseCrossingSign => VariantData {
                 sprite: ((2., 2.), (1., 1.)),
@@ -166,6 +297,8 @@ impl Variant {
                 health: 25,
                 spawn_count: 1,
                 guaranteed_spawns: [Some(AnimalVariant::Horse), None, None],
+                drops: &[],
+                behaviour: Behaviour::None,
             },
             Variant::House1 => VariantData {
                 sprite: ((1., 1.), (2., 1.)),
@@ -174,6 +307,8 @@ impl Variant {
                 health: 100,
                 spawn_count: 2,
                 guaranteed_spawns: [Some(AnimalVariant::Duck), None, None],
+                drops: HOME_DROPS,
+                behaviour: Behaviour::None,
             },
             Variant::House2 => VariantData {
                 sprite: ((4., 4.), (2., 1.)),
@@ -182,6 +317,19 @@ impl Variant {
                 health: 100,
                 spawn_count: 2,
                 guaranteed_spawns: [Some(AnimalVariant::Cat), None, None],
+                drops: HOME_DROPS,
+                behaviour: Behaviour::None,
+            },
+            // the stop sign doubles as the lever, greyed out once pulled like gates are tinted
+            Variant::Lever => VariantData {
+                sprite: ((3., 1.), (1., 1.)),
+                size: (64., 64.),
+                offset: (-1., -55.),
+                health: 0,
+                spawn_count: 0,
+                guaranteed_spawns: [None, None, None],
+                drops: &[],
+                behaviour: Behaviour::Lever,
             },
             Variant::OilBarrel => VariantData {
                 sprite: ((6., 1.), (1., 1.)),
@@ -190,6 +338,12 @@ impl Variant {
                 health: 100,
                 spawn_count: 2,
                 guaranteed_spawns: [Some(AnimalVariant::Kuma), None, None],
+                drops: &[],
+                behaviour: Behaviour::Explode {
+                    radius: 300.,
+                    impulse: 500.,
+                    damage: 100,
+                },
             },
             Variant::Outhouse => VariantData {
                 sprite: ((2., 4.), (1., 1.)),
@@ -198,6 +352,8 @@ impl Variant {
                 health: 10,
                 spawn_count: 1,
                 guaranteed_spawns: [Some(AnimalVariant::Poop), None, None],
+                drops: TOILET_DROPS,
+                behaviour: Behaviour::None,
             },
             Variant::Portapotty => VariantData {
                 sprite: ((3., 4.), (1., 1.)),
@@ -206,6 +362,8 @@ impl Variant {
                 health: 10,
                 spawn_count: 1,
                 guaranteed_spawns: [Some(AnimalVariant::Poop), None, None],
+                drops: TOILET_DROPS,
+                behaviour: Behaviour::None,
             },
             Variant::Stable => VariantData {
                 sprite: ((1., 0.), (1., 1.)),
@@ -214,6 +372,8 @@ impl Variant {
                 health: 10,
                 spawn_count: 2,
                 guaranteed_spawns: [Some(AnimalVariant::Horse), None, None],
+                drops: STABLE_DROPS,
+                behaviour: Behaviour::None,
             },
             Variant::StableDouble => VariantData {
                 sprite: ((3., 3.), (2., 1.)),
@@ -222,6 +382,8 @@ impl Variant {
                 health: 100,
                 spawn_count: 3,
                 guaranteed_spawns: [Some(AnimalVariant::Horse), Some(AnimalVariant::Horse), None],
+                drops: STABLE_DROPS,
+                behaviour: Behaviour::None,
             },
             Variant::StableWide => VariantData {
                 sprite: ((5., 3.), (2., 1.)),
@@ -230,6 +392,8 @@ impl Variant {
                 health: 100,
                 spawn_count: 3,
                 guaranteed_spawns: [Some(AnimalVariant::Horse), Some(AnimalVariant::Horse), None],
+                drops: STABLE_DROPS,
+                behaviour: Behaviour::None,
             },
             Variant::StopSign => VariantData {
                 sprite: ((3., 1.), (1., 1.)),
@@ -238,6 +402,8 @@ impl Variant {
                 health: 10,
                 spawn_count: 2,
                 guaranteed_spawns: [Some(AnimalVariant::Dog), None, None],
+                drops: &[],
+                behaviour: Behaviour::None,
             },
             Variant::YieldSign => VariantData {
                 sprite: ((1., 2.), (1., 1.)),
@@ -246,44 +412,113 @@ impl Variant {
                 health: 10,
                 spawn_count: 1,
                 guaranteed_spawns: [Some(AnimalVariant::Cat), None, None],
+                drops: &[],
+                behaviour: Behaviour::None,
             },
         }
     }
 }
 
 impl Building {
+    pub const VARIANTS: [Variant; 25] = [
+        Variant::Barn,
+        Variant::Car,
+        Variant::ConcreteWallH,
+        Variant::ConcreteWallV,
+        Variant::DownWithHorses,
+        Variant::FeedingTrough,
+        Variant::FenceH,
+        Variant::FenceV,
+        Variant::GateH,
+        Variant::GateV,
+        Variant::Garage,
+        Variant::HayBaleH,
+        Variant::HayBaleV,
+        Variant::HorseCrossingSign,
+        Variant::House1,
+        Variant::House2,
+        Variant::Lever,
+        Variant::OilBarrel,
+        Variant::Outhouse,
+        Variant::Portapotty,
+        Variant::Stable,
+        Variant::StableDouble,
+        Variant::StableWide,
+        Variant::StopSign,
+        Variant::YieldSign,
+    ];
+
     pub fn new(
         variant: Variant,
         idx: GenerationalIndex,
-        res: &mut Resources,
+        world: &mut World,
         position: Vec2,
     ) -> Self {
-        let variant = variant.to_data();
-        let sprite = res
-            .assets
-            .buildings
-            .multisprite(variant.sprite.0.into(), variant.sprite.1.into());
-        let collider = physics::cuboid(variant.size.into());
-        let handle = res.physics.add_static(idx, collider, position);
+        let data = variant.to_data();
+        let collider = physics::cuboid(data.size.into());
+        let handle = world.physics.add_static(idx, collider, position);
 
         Building {
             idx,
-            sprite,
             handle,
-            offset: variant.offset.into(),
-            status: if variant.health == 0 {
+            variant,
+            position,
+            offset: data.offset.into(),
+            status: if data.health == 0 {
                 Status::Indestructible
             } else {
                 Status::Destructible {
                     health: Health::new(
-                        variant.health.into(),
+                        data.health.into(),
                         HEALTH_BAR_SIZE.into(),
                         HEALTH_BAR_OFFSET.into(),
                     ),
                 }
             },
-            spawn_count: variant.spawn_count,
-            guaranteed_spawns: variant.guaranteed_spawns,
+            spawn_count: data.spawn_count,
+            guaranteed_spawns: data.guaranteed_spawns,
+            is_pulled: false,
+            tag: None,
+        }
+    }
+
+    pub fn with_tag(self, tag: String) -> Self {
+        Building {
+            tag: Some(tag),
+            ..self
+        }
+    }
+
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    /// Buildings never move, so unlike the collider this is still around once destroyed
+    pub fn get_position(&self) -> Vec2 {
+        self.position
+    }
+
+    pub fn is_destructible(&self) -> bool {
+        matches!(self.status, Status::Destructible { .. })
+    }
+
+    pub fn is_gate(&self) -> bool {
+        matches!(self.variant.to_data().behaviour, Behaviour::Gate)
+    }
+
+    /// Breaks the gate apart, on the next update
+    pub fn open(&mut self) {
+        self.status = Status::Destroyed;
+    }
+
+    /// Returns the range to open gates in, if this is a lever that hasn't been pulled yet
+    pub fn pull(&mut self) -> Option<f32> {
+        match self.variant.to_data().behaviour {
+            Behaviour::Lever if !self.is_pulled => {
+                self.is_pulled = true;
+                Some(LEVER_RANGE)
+            }
+            _ => None,
         }
     }
 
@@ -292,72 +527,129 @@ impl Building {
         if let Status::Destructible { ref mut health, .. } = &mut self.status {
             health.damage(damage.into());
             if health.is_empty() {
-                self.status = Status::Destroyed {
-                    fade_timer: FADE_TIME,
-                };
+                self.status = Status::Destroyed;
                 return true;
             }
         }
         false
     }
 
-    pub fn update(
-        &mut self,
-        res: &mut Resources,
-        animals: &mut Entities<Animal, { groups::ANIMAL }>,
-    ) {
+    pub fn update(&mut self, world: &mut World) {
         match self.status {
-            Status::Destructible { ref mut health, .. } => health.update(res.delta),
-            Status::Destroyed { ref mut fade_timer } => {
-                *fade_timer -= res.delta;
-                if *fade_timer < 0. {
-                    let origin = res.physics.get_position(self.handle);
-
-                    res.physics.remove(self.handle);
-                    res.deleted.push(self.idx);
-
-                    // spawn random animals
-                    let mut remaining = self.spawn_count as i8;
-                    for variant in self.guaranteed_spawns {
-                        if let Some(variant) = variant {
-                            remaining -= 1;
-                            let position = random_position(origin, SPAWN_MAX_OFFSET);
-                            animals.push(|idx| Animal::new(variant, idx, res, position));
-                        } else {
-                            break;
-                        }
-                    }
-                    while remaining > 0 {
+            Status::Destructible { ref mut health, .. } => health.update(world.delta),
+            Status::Destroyed => {
+                let origin = world.physics.get_position(self.handle);
+
+                world.physics.remove(self.handle);
+                world.deleted.push(self.idx);
+                // after breaking apart, so that explosions scatter the debris too
+                self.break_apart(world, origin);
+                match self.variant.to_data().behaviour {
+                    Behaviour::Explode {
+                        radius,
+                        impulse,
+                        damage,
+                    } => world.spawned.push(Spawn::Explosion {
+                        position: origin,
+                        radius,
+                        impulse,
+                        damage,
+                    }),
+                    Behaviour::Burn {
+                        radius,
+                        damage,
+                        duration,
+                    } => world.spawned.push(Spawn::Fire {
+                        position: origin,
+                        radius,
+                        damage,
+                        duration,
+                    }),
+                    _ => {}
+                }
+
+                // spawn animals
+                let mut remaining = self.spawn_count as i8;
+                for variant in self.guaranteed_spawns {
+                    if let Some(variant) = variant {
                         remaining -= 1;
-                        let position = random_position(origin, SPAWN_MAX_OFFSET);
-                        animals.push(|idx| Animal::random(idx, res, position));
+                        let position = random_position(&mut world.rng, origin, SPAWN_MAX_OFFSET);
+                        world.spawned.push(Spawn::Animal(variant, position));
+                    } else {
+                        break;
                     }
                 }
+                while remaining > 0 {
+                    remaining -= 1;
+                    let position = random_position(&mut world.rng, origin, SPAWN_MAX_OFFSET);
+                    world.spawned.push(Spawn::Drop(self.variant, position));
+                }
             }
             _ => {}
         }
     }
 
-    pub fn draw(&self, res: &Resources) {
-        let position = res.physics.get_position(self.handle);
-        let rotation = res.physics.get_rotation(self.handle);
-        match self.status {
-            Status::Indestructible => self.sprite.draw(position + self.offset, rotation),
-            Status::Destructible { ref health } => {
-                self.sprite.draw(position + self.offset, rotation);
-                health.draw(position);
-            }
-            Status::Destroyed { fade_timer } => {
-                let alpha = fade_timer / FADE_TIME;
-                self.sprite
-                    .draw_alpha(position + self.offset, rotation, alpha);
+    pub fn draw(&self, res: &Resources, world: &World) {
+        let position = world.physics.get_position(self.handle);
+        let rotation = world.physics.get_rotation(self.handle);
+        let data = self.variant.to_data();
+        let (sprite_position, sprite_size) = data.sprite;
+        let sprite = res
+            .assets
+            .buildings
+            .multisprite(sprite_position.into(), sprite_size.into());
+        let tint = match data.behaviour {
+            Behaviour::Gate => GATE_TINT,
+            Behaviour::Lever if self.is_pulled => PULLED_LEVER_TINT,
+            _ => WHITE,
+        };
+        sprite.draw_tint(position + self.offset, rotation, tint);
+        if let Status::Destructible { ref health } = self.status {
+            health.draw(position);
+        }
+    }
+
+    /// Carves the sprite into chunks of debris, covering the collider and whatever of the sprite
+    /// is drawn above it
+    fn break_apart(&self, world: &mut World, origin: Vec2) {
+        let data = self.variant.to_data();
+        let (sprite_position, sprite_size) = data.sprite;
+        let sprite_size = Vec2::from(sprite_size) * BUILDING_SPRITE_SIZE;
+        let sprite_min = origin + self.offset - sprite_size / 2.;
+        let sprite_max = sprite_min + sprite_size;
+
+        let half_size = Vec2::from(data.size) / 2.;
+        let min = vec2((origin.x - half_size.x).max(sprite_min.x), sprite_min.y);
+        let max = (origin + half_size).min(sprite_max);
+        let area = max - min;
+        if area.x <= 0. || area.y <= 0. {
+            return;
+        }
+
+        let columns = (area.x / DEBRIS_SIZE).ceil();
+        let rows = (area.y / DEBRIS_SIZE).ceil();
+        let size = area / vec2(columns, rows);
+        // from world to spritesheet coordinates
+        let source_offset = Vec2::from(sprite_position) * BUILDING_SPRITE_SIZE - sprite_min;
+
+        for row in 0..rows as u8 {
+            for column in 0..columns as u8 {
+                let corner = min + size * vec2(column as f32, row as f32);
+                let position = corner + size / 2.;
+                let speed = world.debris_rng.gen_range(DEBRIS_SPEED.0, DEBRIS_SPEED.1);
+                let source = corner + source_offset;
+                world.spawned.push(Spawn::Debris {
+                    position,
+                    velocity: (position - origin).normalize_or_zero() * speed,
+                    source: Rect::new(source.x, source.y, size.x, size.y),
+                });
             }
         }
     }
 }
 
-fn random_position(center: Vec2, offset: f32) -> Vec2 {
-    let dx = rand::gen_range(-offset, offset);
-    let dy = rand::gen_range(-offset, offset);
+fn random_position(rng: &mut Rng, center: Vec2, offset: f32) -> Vec2 {
+    let dx = rng.gen_range(-offset, offset);
+    let dy = rng.gen_range(-offset, offset);
     center + (dx, dy).into()
 }
@@ -1,36 +1,169 @@
 use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{input::MouseDrag, rng::Rng};
+
+const ZOOM_FACTOR: f32 = 1.05;
+const MIN_ZOOM: f32 = 0.00035;
+const MAX_ZOOM: f32 = 0.005;
+
+/// How much of the way to what's being followed the camera moves every second
+const FOLLOW_SPEED: f32 = 4.;
+/// Kept around the animals when zooming to fit them, in world units
+const FIT_MARGIN: f32 = 480.;
+
+/// How much trauma wears off every second
+const TRAUMA_DECAY: f32 = 1.5;
+/// The furthest the camera is shaken, relative to the size of the view
+const MAX_SHAKE: f32 = 0.04;
+
+/// What the camera keeps up with on its own, until the player pans it
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Follow {
+    Off,
+    DeathBall,
+    /// Zooming to fit every animal in the deathball
+    Animals,
+}
+
+pub const FOLLOW_MODES: [Follow; 3] = [Follow::Off, Follow::DeathBall, Follow::Animals];
 
 pub struct Camera {
     pub target: Vec2,
     pub zoom: f32,
+    /// The target is kept inside, if there are any
+    bounds: Option<Rect>,
+    /// From 0 to 1, shakes the camera more the higher it is
+    trauma: f32,
+    shake: Vec2,
 }
 
 impl Camera {
     pub fn new(target: Vec2, zoom: f32) -> Self {
-        Self { target, zoom }
+        Self {
+            target,
+            zoom,
+            bounds: None,
+            trauma: 0.,
+            shake: Vec2::ZERO,
+        }
+    }
+
+    pub fn with_bounds(mut self, bounds: Option<Rect>) -> Self {
+        self.bounds = bounds;
+        self.keep_in_bounds();
+        self
     }
 
-    fn get_macroquad_camera(&self) -> Camera2D {
+    fn get_macroquad_camera(&self, target: Vec2) -> Camera2D {
         Camera2D {
-            target: self.target,
+            target,
             zoom: vec2(self.zoom, -self.zoom * screen_width() / screen_height()),
             ..Default::default()
         }
     }
 
     // fn world_to_screen(&self, point: Vec2) -> Vec2 {
-    //     self.get_macroquad_camera().world_to_screen(point)
+    //     self.get_macroquad_camera(self.target).world_to_screen(point)
     // }
 
     pub fn screen_to_world(&self, point: Vec2) -> Vec2 {
-        self.get_macroquad_camera().screen_to_world(point)
+        self.get_macroquad_camera(self.target)
+            .screen_to_world(point)
+    }
+
+    /// Pans by dragging with the mouse if there is a drag, by the given amount otherwise
+    pub fn pan_and_zoom(&mut self, drag: Option<MouseDrag>, pan: Vec2, zoom: Option<f32>) {
+        match drag {
+            Some(drag) => {
+                let previous = self.screen_to_world(drag.previous);
+                let current = self.screen_to_world(drag.current);
+                self.target += previous - current;
+            }
+            None => self.target += pan,
+        }
+
+        if let Some(amount) = zoom {
+            self.zoom = (self.zoom * ZOOM_FACTOR.powf(amount)).clamp(MIN_ZOOM, MAX_ZOOM);
+        }
+        self.keep_in_bounds();
+    }
+
+    /// Smoothly moves towards the position, and zooms to fit the area if there is one
+    pub fn follow(&mut self, delta: f32, position: Vec2, fit: Option<Rect>) {
+        let amount = 1. - (-FOLLOW_SPEED * delta).exp();
+        self.target = self.target.lerp(position, amount);
+
+        if let Some(area) = fit {
+            let size = area.size() + Vec2::splat(FIT_MARGIN * 2.);
+            let aspect = screen_width() / screen_height();
+            let zoom = (2. / size.x).min(2. / (size.y * aspect));
+            let zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+            self.zoom += (zoom - self.zoom) * amount;
+        }
+        self.keep_in_bounds();
+    }
+
+    /// Adds to how much the camera shakes, up to the most it can
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).min(1.);
+    }
+
+    /// Shakes the camera, the shaking being cosmetic so it draws from the cosmetic stream
+    pub fn update(&mut self, delta: f32, rng: &mut Rng) {
+        self.trauma = (self.trauma - TRAUMA_DECAY * delta).max(0.);
+
+        // squared so that small amounts of trauma barely shake
+        let shake = self.trauma * self.trauma * MAX_SHAKE / self.zoom;
+        self.shake = vec2(rng.gen_range(-1., 1.), rng.gen_range(-1., 1.)) * shake;
     }
 
     pub fn enable(&self) {
-        set_camera(&self.get_macroquad_camera());
+        set_camera(&self.get_macroquad_camera(self.target + self.shake));
     }
 
     pub fn disable(&self) {
         set_default_camera();
     }
+
+    fn keep_in_bounds(&mut self) {
+        if let Some(bounds) = self.bounds {
+            self.target = self
+                .target
+                .clamp(bounds.point(), bounds.point() + bounds.size());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_keep_the_target_within_bounds() {
+        let bounds = Rect::new(-100., -50., 200., 100.);
+        let mut camera = Camera::new(vec2(500., 0.), 0.001).with_bounds(Some(bounds));
+        assert_eq!(camera.target, vec2(100., 0.));
+
+        camera.pan_and_zoom(None, vec2(-1000., -1000.), None);
+        assert_eq!(camera.target, vec2(-100., -50.));
+
+        let mut camera = Camera::new(Vec2::ZERO, 0.001);
+        camera.pan_and_zoom(None, vec2(-1000., -1000.), None);
+        assert_eq!(camera.target, vec2(-1000., -1000.));
+    }
+
+    #[test]
+    fn should_stop_shaking_once_trauma_wears_off() {
+        let mut camera = Camera::new(Vec2::ZERO, 0.001);
+        let mut rng = Rng::new(0);
+        camera.add_trauma(0.5);
+        camera.add_trauma(0.75);
+        camera.update(0.1, &mut rng);
+        assert!(camera.shake != Vec2::ZERO);
+        assert!(camera.shake.abs().max_element() <= 0.85 * 0.85 * MAX_SHAKE / camera.zoom);
+
+        camera.update(1., &mut rng);
+        assert_eq!(camera.shake, Vec2::ZERO);
+    }
 }
@@ -0,0 +1,69 @@
+//! The chunks that buildings break into once destroyed, see `buildings`.
+
+use macroquad::prelude::*;
+
+use crate::{entities::GenerationalIndex, physics, simulation::World, Resources};
+
+const LIFETIME: f32 = 4.;
+const FADE_TIME: f32 = 1.;
+const DAMPING: f32 = 2.;
+/// Per pixel of area, so that bigger chunks are harder to push around
+const DENSITY: f32 = 0.0005;
+/// Shrinks the collider so that neighbouring chunks start off (mostly) apart
+const COLLIDER_SCALE: f32 = 0.8;
+
+pub struct Debris {
+    idx: GenerationalIndex,
+    handle: physics::DynamicHandle,
+    /// The part of the `buildings` spritesheet this chunk was carved from, in pixels
+    source: Rect,
+    timer: f32,
+}
+
+impl Debris {
+    pub fn new(
+        idx: GenerationalIndex,
+        world: &mut World,
+        position: Vec2,
+        velocity: Vec2,
+        source: Rect,
+    ) -> Self {
+        let size = source.size();
+        let collider = physics::cuboid(size * COLLIDER_SCALE)
+            .mass(size.x * size.y * DENSITY)
+            .linear_damping(DAMPING);
+        let handle = world.physics.add_dynamic(idx, collider, position);
+        world.physics.set_linear_velocity(handle, velocity);
+
+        Debris {
+            idx,
+            handle,
+            source,
+            timer: LIFETIME,
+        }
+    }
+
+    pub fn get_position(&self, world: &World) -> Vec2 {
+        world.physics.get_position(self.handle)
+    }
+
+    pub fn push(&self, world: &mut World, impulse: Vec2) {
+        world.physics.apply_impulse(self.handle, impulse);
+    }
+
+    pub fn update(&mut self, world: &mut World) {
+        self.timer -= world.delta;
+        if self.timer < 0. {
+            world.physics.remove(self.handle);
+            world.deleted.push(self.idx);
+        }
+    }
+
+    pub fn draw(&self, res: &Resources, world: &World) {
+        let position = world.physics.get_position(self.handle);
+        let rotation = world.physics.get_rotation(self.handle);
+        let alpha = (self.timer / FADE_TIME).min(1.);
+        let sprite = res.assets.buildings.region(self.source);
+        sprite.draw_alpha(position, rotation, alpha);
+    }
+}
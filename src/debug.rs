@@ -0,0 +1,196 @@
+//! Toggles and cheats for testing, in a window opened with F1. Release builds can't open it, so
+//! everything stays at its default there.
+//!
+//! Commands that change a level are queued up for the `Combat` scene to carry out, as it's the one
+//! with the simulation. It records them in the replay as `Cheat`s, so that they play back too.
+
+use crate::{
+    animals::{Animal, Variant as AnimalVariant},
+    buildings::Building,
+    enemies::Enemy,
+    input::Input,
+    levels::Level,
+    scenes::{self, SceneChange},
+    simulation::Spawnable,
+    time_scale::TimeScale,
+    Resources,
+};
+
+const LEVELS: [Level; 6] = [
+    Level::Test,
+    Level::Tutorial,
+    Level::Scenario1,
+    Level::Scenario2,
+    Level::Final,
+    Level::Endless,
+];
+
+pub enum Command {
+    /// At the cursor
+    Spawn(Spawnable),
+    KillEnemies,
+    SetDeathBallSize(u8),
+}
+
+pub struct Debug {
+    pub is_open: bool,
+    pub auto_complete_objectives: bool,
+    pub disable_bgm: bool,
+    pub disable_sfx: bool,
+    pub draw_colliders: bool,
+    pub enable_editor: bool,
+    pub enable_level_select: bool,
+    pub show_fps: bool,
+    commands: Vec<Command>,
+    spawnable: Spawnable,
+    death_ball_size: u8,
+}
+
+impl Debug {
+    pub fn new() -> Self {
+        Debug {
+            is_open: false,
+            auto_complete_objectives: false,
+            disable_bgm: false,
+            disable_sfx: false,
+            draw_colliders: false,
+            enable_editor: false,
+            enable_level_select: false,
+            show_fps: false,
+            commands: Vec::new(),
+            spawnable: Spawnable::Animal(AnimalVariant::Horse),
+            death_ball_size: 10,
+        }
+    }
+
+    /// Takes the commands queued since the last time this was called
+    pub fn drain_commands(&mut self) -> impl Iterator<Item = Command> + '_ {
+        self.commands.drain(..)
+    }
+
+    /// Handles the shortcuts, which work with the window closed too
+    pub fn update(&mut self, input: &Input, time_scale: &mut TimeScale) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+        if input.toggle_debug() {
+            self.is_open = !self.is_open;
+        }
+        if input.debug_spawn() {
+            self.commands.push(Command::Spawn(self.spawnable));
+        }
+        if time_scale.is_stepping && input.debug_step() {
+            time_scale.step();
+        }
+    }
+}
+
+/// Draws the debug window if it's open, returns the level to jump to if one was picked
+pub fn update_ui(res: &mut Resources, ctx: &egui::CtxRef) -> SceneChange {
+    use egui::*;
+
+    // anything not carried out by now was queued outside of a level
+    res.debug.commands.clear();
+
+    let mut scene_change = SceneChange::None;
+    if !res.debug.is_open {
+        return scene_change;
+    }
+
+    let debug = &mut res.debug;
+    let time_scale = &mut res.time_scale;
+    let (disable_bgm, disable_sfx) = (debug.disable_bgm, debug.disable_sfx);
+    let mut level_to_load = None;
+    let mut open_editor = false;
+
+    Window::new("Debug")
+        .resizable(false)
+        .anchor(egui::Align2::RIGHT_TOP, (-8., 64.))
+        .show(ctx, |ui| {
+            ui.checkbox(
+                &mut debug.auto_complete_objectives,
+                "Auto Complete Objectives",
+            );
+            ui.checkbox(&mut debug.disable_bgm, "Disable Music");
+            ui.checkbox(&mut debug.disable_sfx, "Disable Sound Effects");
+            ui.checkbox(&mut debug.draw_colliders, "Draw Colliders");
+            ui.checkbox(&mut debug.enable_editor, "Enable Editor");
+            ui.checkbox(&mut debug.enable_level_select, "Enable Level Select");
+            ui.checkbox(&mut debug.show_fps, "Show FPS");
+
+            ui.separator();
+
+            ui.add(Slider::new(&mut time_scale.base, 0.1..=4.0).text("Time Scale"));
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut time_scale.is_stepping, "Step Through");
+                if ui
+                    .add_enabled(time_scale.is_stepping, Button::new("Step (F3)"))
+                    .clicked()
+                {
+                    time_scale.step();
+                }
+            });
+
+            ui.separator();
+
+            ComboBox::from_label("Spawn")
+                .selected_text(spawnable_name(debug.spawnable))
+                .show_ui(ui, |ui| {
+                    let spawnables = (Animal::VARIANTS.iter().map(|&v| Spawnable::Animal(v)))
+                        .chain(Enemy::VARIANTS.iter().map(|&v| Spawnable::Enemy(v)))
+                        .chain(Building::VARIANTS.iter().map(|&v| Spawnable::Building(v)));
+                    for spawnable in spawnables {
+                        let name = spawnable_name(spawnable);
+                        ui.selectable_value(&mut debug.spawnable, spawnable, name);
+                    }
+                });
+            ui.label("Press F2 to spawn it at the cursor.");
+            if ui.button("Kill All Enemies").clicked() {
+                debug.commands.push(Command::KillEnemies);
+            }
+            ui.horizontal(|ui| {
+                ui.add(DragValue::new(&mut debug.death_ball_size).clamp_range(0..=u8::MAX));
+                if ui.button("Set Deathball Count").clicked() {
+                    let size = debug.death_ball_size;
+                    debug.commands.push(Command::SetDeathBallSize(size));
+                }
+            });
+
+            ui.separator();
+
+            ComboBox::from_label("Jump to Level")
+                .selected_text("Pick a Level")
+                .show_ui(ui, |ui| {
+                    for level in LEVELS {
+                        if ui.selectable_label(false, format!("{:?}", level)).clicked() {
+                            level_to_load = Some(level);
+                        }
+                    }
+                });
+            if ui.button("Open Editor").clicked() {
+                open_editor = true;
+            }
+        });
+
+    if debug.disable_bgm != disable_bgm {
+        res.audio.bgm.set_enabled(!debug.disable_bgm);
+    }
+    if debug.disable_sfx != disable_sfx {
+        res.audio.set_sfx_enabled(!debug.disable_sfx);
+    }
+
+    if let Some(level) = level_to_load {
+        scene_change = SceneChange::Change(scenes::Combat::boxed(res, level));
+    } else if open_editor {
+        scene_change = SceneChange::Change(scenes::Editor::boxed());
+    }
+    scene_change
+}
+
+fn spawnable_name(spawnable: Spawnable) -> String {
+    match spawnable {
+        Spawnable::Animal(variant) => format!("Animal: {:?}", variant),
+        Spawnable::Enemy(variant) => format!("Enemy: {:?}", variant),
+        Spawnable::Building(variant) => format!("Building: {:?}", variant),
+    }
+}
@@ -0,0 +1,128 @@
+//! Which animals destroyed buildings drop, on top of their guaranteed spawns.
+//!
+//! Each building variant picks from its own weighted table, or from every animal weighted by
+//! rarity if it doesn't have one. Levels can replace any table, and cap how many of each animal
+//! can be dropped (legendary animals are capped to one by default).
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    animals::{Animal, Variant as AnimalVariant},
+    buildings::Variant as BuildingVariant,
+    rng::Rng,
+};
+
+/// A level's overrides, see `LevelDefinition`
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DropRules {
+    /// Replaces the building variant's own table
+    pub tables: HashMap<BuildingVariant, Vec<(AnimalVariant, u32)>>,
+    /// Replaces the cap that comes with the animal's rarity
+    pub caps: HashMap<AnimalVariant, u8>,
+}
+
+pub struct Drops {
+    rules: DropRules,
+    /// Every animal that buildings have spawned so far, including guaranteed spawns
+    found: BTreeMap<AnimalVariant, u8>,
+}
+
+impl Drops {
+    pub fn new(rules: DropRules) -> Self {
+        Drops {
+            rules,
+            found: BTreeMap::new(),
+        }
+    }
+
+    pub fn found(&self) -> &BTreeMap<AnimalVariant, u8> {
+        &self.found
+    }
+
+    pub fn add_found(&mut self, variant: AnimalVariant) {
+        *self.found.entry(variant).or_default() += 1;
+    }
+
+    /// Picks an animal for the building to drop, skipping any that have reached their cap.
+    /// Returns `None` if there's nothing left to pick.
+    pub fn roll(&mut self, building: BuildingVariant, rng: &mut Rng) -> Option<AnimalVariant> {
+        let table: Vec<(AnimalVariant, u32)> = match self.rules.tables.get(&building) {
+            Some(table) => table.clone(),
+            None if !building.drops().is_empty() => building.drops().to_vec(),
+            None => Animal::VARIANTS
+                .iter()
+                .map(|&variant| (variant, variant.rarity().weight()))
+                .collect(),
+        };
+        let table: Vec<_> = table
+            .into_iter()
+            .filter(|&(variant, weight)| weight > 0 && !self.is_capped(variant))
+            .collect();
+
+        let total: u32 = table.iter().map(|(_, weight)| weight).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut roll = rng.gen_range(0, total as usize) as u32;
+        for (variant, weight) in table {
+            if roll < weight {
+                self.add_found(variant);
+                return Some(variant);
+            }
+            roll -= weight;
+        }
+        unreachable!("the roll is always less than the total weight")
+    }
+
+    fn is_capped(&self, variant: AnimalVariant) -> bool {
+        let cap = match self.rules.caps.get(&variant) {
+            Some(&cap) => Some(cap),
+            None => variant.rarity().cap(),
+        };
+        let found = self.found.get(&variant).copied().unwrap_or(0);
+        cap.is_some_and(|cap| found >= cap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_only_pick_from_the_buildings_table() {
+        let mut drops = Drops::new(DropRules::default());
+        let mut rng = Rng::new(0);
+        for _ in 0..100 {
+            let variant = drops.roll(BuildingVariant::Outhouse, &mut rng).unwrap();
+            assert!(BuildingVariant::Outhouse
+                .drops()
+                .iter()
+                .any(|&(other, _)| other == variant));
+        }
+        assert_eq!(drops.found().values().sum::<u8>(), 100);
+    }
+
+    #[test]
+    fn should_stop_picking_animals_once_capped() {
+        let mut rules = DropRules::default();
+        rules.tables.insert(
+            BuildingVariant::Barn,
+            vec![(AnimalVariant::RubberDucky, 1), (AnimalVariant::Loaf, 1)],
+        );
+        rules.caps.insert(AnimalVariant::Loaf, 2);
+        let mut drops = Drops::new(rules);
+        let mut rng = Rng::new(0);
+        let mut rolls = Vec::new();
+        for _ in 0..10 {
+            rolls.push(drops.roll(BuildingVariant::Barn, &mut rng));
+        }
+
+        // the ducky is legendary, so capped to one by default
+        assert_eq!(drops.found()[&AnimalVariant::RubberDucky], 1);
+        assert_eq!(drops.found()[&AnimalVariant::Loaf], 2);
+        assert_eq!(rolls.iter().filter(|roll| roll.is_some()).count(), 3);
+    }
+}
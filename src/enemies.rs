@@ -1,7 +1,17 @@
 use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    entities::GenerationalIndex, groups, health::Health, physics, spritesheet::Sprite, Resources,
+    ai::{self, Ai},
+    animals::{Ability, Animal},
+    boss::{self, Boss, BossStatus, Pattern, Phase},
+    death_ball::DeathBall,
+    entities::{Entities, GenerationalIndex},
+    groups,
+    health::Health,
+    physics,
+    simulation::{Spawn, World},
+    Resources,
 };
 
 const FADE_TIME: f32 = 1.;
@@ -12,12 +22,17 @@ const PRE_ATTACK_DURATION: f32 = PRE_ATTACK_FLASH * 3.;
 const ATTACK_DURATION: f32 = 1.;
 const ATTACK_OFFSET_START: f32 = 6.;
 const ATTACK_OFFSET_END: f32 = 56.;
+const ATTACK_RADIUS: f32 = 16.;
+
+const KNOCKBACK_DURATION: f32 = 0.3;
+const POISON_TINT: Color = Color::new(0.6, 1., 0.5, 1.);
+const SLOW_TINT: Color = Color::new(0.8, 0.65, 0.5, 1.);
 
 const HEALTH_BAR_SIZE: (f32, f32) = (32., 14.);
 const HEALTH_BAR_OFFSET: (f32, f32) = (16., 36.);
 
 enum Status {
-    Alive { health: Health, speed: f32 },
+    Alive { health: Health, ai: Ai },
     Dead { fade_timer: f32 },
 }
 
@@ -26,13 +41,19 @@ pub struct Enemy {
     handle: physics::DynamicHandle,
     sensor_handle: physics::SensorHandle,
     nearby_animals: Vec<physics::Handle>,
-    sprite: Sprite,
+    variant: Variant,
     status: Status,
     attack: Attack,
+    boss: Option<Boss>,
+    /// The gun and the time since it last fired
+    gun: Option<(Gun, f32)>,
+    afflictions: Afflictions,
     pub attack_impulse: f32,
+    /// Dealt to animals by the attack, and by boss patterns
+    pub attack_damage: u8,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
 pub enum Variant {
     Demon,
     DemonBoss,
@@ -49,10 +70,64 @@ struct VariantData {
     speed: f32,
     detection_range: f32,
     attack_impulse: f32,
+    attack_damage: u8,
     attack_cooldown: f32,
+    ai: ai::Profile,
+    boss: Option<&'static boss::Definition>,
+    gun: Option<Gun>,
 }
 
+/// Fires projectiles at whatever the enemy is going after
+#[derive(Clone, Copy)]
+struct Gun {
+    speed: f32,
+    /// The total angle projectiles may stray from the target by, in radians
+    spread: f32,
+    cooldown: f32,
+    /// Kept low against animal health, as a gun fires at the death ball every few seconds from
+    /// out of reach, and anything more wipes it out before it can close the distance
+    damage: u8,
+}
+
+const DEMON_LORD: boss::Definition = boss::Definition {
+    name: "Demon Lord",
+    phases: &[
+        Phase {
+            below: 1.,
+            patterns: &[Pattern::Shockwave],
+            cooldown: 6.,
+        },
+        Phase {
+            below: 2. / 3.,
+            patterns: &[
+                Pattern::Shockwave,
+                Pattern::Summon {
+                    variant: Variant::Demon,
+                    count: 2,
+                },
+            ],
+            cooldown: 5.,
+        },
+        Phase {
+            below: 1. / 3.,
+            patterns: &[
+                Pattern::Charge { speed: 400. },
+                Pattern::Shockwave,
+                Pattern::Summon {
+                    variant: Variant::Demon,
+                    count: 3,
+                },
+            ],
+            cooldown: 3.5,
+        },
+    ],
+};
+
 impl Variant {
+    pub fn is_boss(self) -> bool {
+        self.to_data().boss.is_some()
+    }
+
     fn to_data(self) -> VariantData {
         match self {
             Variant::Demon => VariantData {
@@ -62,7 +137,11 @@ impl Variant {
                 speed: 75.,
                 detection_range: 600.,
                 attack_impulse: 300.,
+                attack_damage: 4,
                 attack_cooldown: 5.,
+                ai: ai::Profile::BRUTE,
+                boss: None,
+                gun: None,
             },
             Variant::DemonBoss => VariantData {
                 scale: 10.,
@@ -71,7 +150,11 @@ impl Variant {
                 speed: 50.,
                 detection_range: 6000.,
                 attack_impulse: 600.,
+                attack_damage: 8,
                 attack_cooldown: 5.,
+                ai: ai::Profile::HUNTER,
+                boss: Some(&DEMON_LORD),
+                gun: None,
             },
             Variant::Farmer => VariantData {
                 scale: 1.,
@@ -80,7 +163,11 @@ impl Variant {
                 speed: 50.,
                 detection_range: 300.,
                 attack_impulse: 300.,
+                attack_damage: 2,
                 attack_cooldown: 10.,
+                ai: ai::Profile::COWARD,
+                boss: None,
+                gun: None,
             },
             Variant::Police => VariantData {
                 scale: 1.,
@@ -89,7 +176,16 @@ impl Variant {
                 speed: 50.,
                 detection_range: 400.,
                 attack_impulse: 300.,
+                attack_damage: 3,
                 attack_cooldown: 10.,
+                ai: ai::Profile::GUARD,
+                boss: None,
+                gun: Some(Gun {
+                    speed: 500.,
+                    spread: 0.3,
+                    cooldown: 3.,
+                    damage: 1,
+                }),
             },
             Variant::Snowman => VariantData {
                 scale: 1.,
@@ -98,7 +194,11 @@ impl Variant {
                 speed: 25.,
                 detection_range: 600.,
                 attack_impulse: 300.,
+                attack_damage: 3,
                 attack_cooldown: 7.,
+                ai: ai::Profile::SENTRY,
+                boss: None,
+                gun: None,
             },
             Variant::Soldier => VariantData {
                 scale: 1.,
@@ -107,38 +207,137 @@ impl Variant {
                 speed: 60.,
                 detection_range: 400.,
                 attack_impulse: 420.,
+                attack_damage: 4,
                 attack_cooldown: 9.,
+                ai: ai::Profile::HUNTER,
+                boss: None,
+                gun: Some(Gun {
+                    speed: 700.,
+                    spread: 0.15,
+                    cooldown: 1.5,
+                    damage: 1,
+                }),
             },
         }
     }
 }
 
+impl Gun {
+    fn fire(&self, world: &mut World, position: Vec2, radius: f32, target: Vec2, impulse: f32) {
+        let towards = target - position;
+        let spread = world.rng.gen_range(-self.spread / 2., self.spread / 2.);
+        let angle = towards.y.atan2(towards.x) + spread;
+        let direction = vec2(angle.cos(), angle.sin());
+        world.spawned.push(Spawn::Projectile {
+            // from the edge of the enemy, rather than from inside it
+            position: position + direction * radius,
+            velocity: direction * self.speed,
+            impulse,
+            damage: self.damage,
+        });
+    }
+}
+
+/// The lingering effects of animals' abilities, each with the time it has left
+#[derive(Default)]
+struct Afflictions {
+    /// Damage per second
+    poison: Option<(f32, f32)>,
+    /// Poison damage that adds up to less than a whole point so far
+    poison_buildup: f32,
+    /// Speed multiplier
+    slow: Option<(f32, f32)>,
+    /// Velocity to move at instead
+    knockback: Option<(Vec2, f32)>,
+}
+
+impl Afflictions {
+    fn add(&mut self, ability: Ability, direction: Vec2, scale: f32) {
+        match ability {
+            Ability::Poison { damage, duration } => self.poison = Some((damage, duration)),
+            Ability::Slow { amount, duration } => self.slow = Some((amount, duration)),
+            Ability::Knockback { speed } => {
+                // bigger enemies get pushed around less
+                let velocity = direction * speed / scale;
+                self.knockback = Some((velocity, KNOCKBACK_DURATION));
+            }
+        }
+    }
+
+    /// Returns the poison damage dealt this step, and the velocity to move at instead of the
+    /// given one
+    fn update(&mut self, delta: f32, mut velocity: Vec2) -> (u8, Vec2) {
+        let mut damage = 0;
+        if let Some(damage_per_second) = tick(&mut self.poison, delta) {
+            self.poison_buildup += damage_per_second * delta;
+            damage = self.poison_buildup as u8;
+            self.poison_buildup -= damage as f32;
+        }
+        if let Some(amount) = tick(&mut self.slow, delta) {
+            velocity *= amount;
+        }
+        if let Some(knockback) = tick(&mut self.knockback, delta) {
+            velocity = knockback;
+        }
+        (damage, velocity)
+    }
+
+    fn tint(&self) -> Option<Color> {
+        if self.poison.is_some() {
+            Some(POISON_TINT)
+        } else if self.slow.is_some() {
+            Some(SLOW_TINT)
+        } else {
+            None
+        }
+    }
+}
+
+/// Counts down the time an affliction has left, returns its value while it lasts
+fn tick<T: Copy>(affliction: &mut Option<(T, f32)>, delta: f32) -> Option<T> {
+    let (value, timer) = affliction.as_mut()?;
+    let value = *value;
+    *timer -= delta;
+    if *timer <= 0. {
+        *affliction = None;
+    }
+    Some(value)
+}
+
 impl Enemy {
+    pub const VARIANTS: [Variant; 6] = [
+        Variant::Demon,
+        Variant::DemonBoss,
+        Variant::Farmer,
+        Variant::Police,
+        Variant::Snowman,
+        Variant::Soldier,
+    ];
+
     pub fn new(
         variant: Variant,
         idx: GenerationalIndex,
-        res: &mut Resources,
+        world: &mut World,
         position: Vec2,
     ) -> Self {
-        let variant = variant.to_data();
-        let scale = variant.scale;
-        let sprite = res
-            .assets
-            .enemies
-            .sprite(variant.sprite.into())
-            .scale(scale);
+        let data = variant.to_data();
+        let scale = data.scale;
 
         // add a dynamic body with very large mass so that we mimic a kinematic body that
         // can't be moved by collisions from animals
         // but will not intersect static bodies
-        let collider = physics::ball(16. * scale)
+        let radius = 16. * scale;
+        let collider = physics::ball(radius)
             .mass(1_000_000_000.)
             .lock_rotations()
             .contact_events();
-        let handle = res.physics.add_dynamic(idx, collider, position);
+        let handle = world.physics.add_dynamic(idx, collider, position);
 
-        let collider = physics::ball(variant.detection_range * scale).intersection_events();
-        let sensor_handle = res.physics.add_sensor(idx, collider, position);
+        let collider = physics::ball(data.detection_range * scale).intersection_events();
+        let sensor_handle = world.physics.add_sensor(idx, collider, position);
+
+        // far enough to be able to hit with the end of the attack
+        let attack_range = (ATTACK_OFFSET_END + ATTACK_RADIUS) * scale;
 
         let mut health_bar_size = Vec2::from(HEALTH_BAR_SIZE);
         health_bar_size.x *= scale;
@@ -147,15 +346,21 @@ impl Enemy {
 
         Enemy {
             idx,
-            sprite,
             handle,
             sensor_handle,
             nearby_animals: Vec::new(),
-            attack: Attack::new(idx, res, scale, variant.attack_cooldown),
-            attack_impulse: variant.attack_impulse,
+            variant,
+            attack: Attack::new(idx, scale, data.attack_cooldown),
+            boss: data
+                .boss
+                .map(|definition| Boss::new(definition, idx, radius)),
+            gun: data.gun.map(|gun| (gun, 0.)),
+            afflictions: Afflictions::default(),
+            attack_impulse: data.attack_impulse,
+            attack_damage: data.attack_damage,
             status: Status::Alive {
-                health: Health::new(variant.health, health_bar_size, health_bar_offset),
-                speed: variant.speed,
+                health: Health::new(data.health, health_bar_size, health_bar_offset),
+                ai: Ai::new(data.ai, position, radius, data.speed, attack_range),
             },
         }
     }
@@ -167,6 +372,38 @@ impl Enemy {
         self.nearby_animals.retain(|a| *a != animal);
     }
 
+    pub fn get_position(&self, world: &World) -> Vec2 {
+        world.physics.get_position(self.handle)
+    }
+
+    /// Dead enemies are left to fade away
+    pub fn is_alive(&self) -> bool {
+        matches!(self.status, Status::Alive { .. })
+    }
+
+    pub fn is_boss(&self) -> bool {
+        self.boss.is_some()
+    }
+
+    /// Only while the boss is alive
+    pub fn boss_status(&self) -> Option<BossStatus> {
+        match (&self.boss, &self.status) {
+            (Some(boss), Status::Alive { health, .. }) => Some(boss.status(health.fraction())),
+            _ => None,
+        }
+    }
+
+    /// Returns whether or not the enemy was still alive to be killed
+    pub fn kill(&mut self) -> bool {
+        if let Status::Alive { .. } = self.status {
+            self.status = Status::Dead {
+                fade_timer: FADE_TIME,
+            };
+            return true;
+        }
+        false
+    }
+
     /// Returns whether or not the enemy was killed
     pub fn damage(&mut self, damage: u8) -> bool {
         if let Status::Alive { ref mut health, .. } = &mut self.status {
@@ -181,65 +418,131 @@ impl Enemy {
         false
     }
 
-    pub fn update(&mut self, res: &mut Resources) {
-        let position = res.physics.get_position(self.handle);
-        self.attack
-            .update(res, position, self.nearby_animals.first());
+    /// Applies an animal's ability, `direction` being the way the animal hit the enemy
+    pub fn afflict(&mut self, ability: Ability, direction: Vec2) {
+        let scale = self.variant.to_data().scale;
+        self.afflictions.add(ability, direction, scale);
+    }
+
+    /// Returns whether or not the enemy was killed (by poison) during the update
+    pub fn update(
+        &mut self,
+        world: &mut World,
+        animals: &Entities<Animal, { groups::ANIMAL }>,
+        death_ball: &DeathBall,
+    ) -> bool {
+        let position = self.get_position(world);
+        let mut poison = 0;
 
         match self.status {
             Status::Alive {
-                speed,
+                ref mut ai,
                 ref mut health,
-                ..
             } => {
-                health.update(res.delta);
+                health.update(world.delta);
 
                 // ensure sensor collider moves with the enemy
-                res.physics.set_position(self.sensor_handle, position);
-
-                // move towards the first nearby animal
-                let velocity = if let Some(first) = self.nearby_animals.first() {
-                    let animal_pos = res.physics.get_position(*first);
-                    (animal_pos - position).normalize_or_zero() * speed
-                } else {
-                    Vec2::ZERO
-                };
-                res.physics.set_linear_velocity(self.handle, velocity);
+                world.physics.set_position(self.sensor_handle, position);
+
+                let targets: Vec<_> = self
+                    .nearby_animals
+                    .iter()
+                    .map(|&handle| {
+                        let animal = &animals[world.physics.get_idx(handle)];
+                        ai::Target {
+                            handle,
+                            position: world.physics.get_position(handle),
+                            strength: animal.damage.into(),
+                        }
+                    })
+                    .collect();
+                let death_ball = death_ball.get_position(world);
+                let decision = ai.update(
+                    world,
+                    position,
+                    health.fraction(),
+                    &targets,
+                    death_ball,
+                    self.attack.is_attacking(),
+                );
+
+                let mut velocity = decision.velocity;
+                if let Some(boss) = &mut self.boss {
+                    let health = health.fraction();
+                    if let Some(pattern_velocity) =
+                        boss.update(world, position, health, decision.target)
+                    {
+                        velocity = pattern_velocity;
+                    }
+                }
+                let (damage, velocity) = self.afflictions.update(world.delta, velocity);
+                poison = damage;
+
+                world.physics.set_linear_velocity(self.handle, velocity);
+                self.attack.update(world, position, decision.attack);
+
+                if let Some((gun, ref mut timer)) = self.gun {
+                    *timer += world.delta;
+                    if let Some(target) = decision.target.filter(|_| *timer >= gun.cooldown) {
+                        *timer = 0.;
+                        let radius = ATTACK_RADIUS * self.variant.to_data().scale;
+                        gun.fire(world, position, radius, target, self.attack_impulse);
+                    }
+                }
             }
             Status::Dead { ref mut fade_timer } => {
-                *fade_timer -= res.delta;
+                self.attack.update(world, position, None);
+                if let Some(boss) = &mut self.boss {
+                    boss.remove(world);
+                }
+                *fade_timer -= world.delta;
                 if *fade_timer < 0. {
-                    self.attack.remove(res);
-                    res.physics.remove(self.handle);
-                    res.physics.remove(self.sensor_handle);
-                    res.deleted.push(self.idx);
+                    self.attack.remove(world);
+                    world.physics.remove(self.handle);
+                    world.physics.remove(self.sensor_handle);
+                    world.deleted.push(self.idx);
                 }
             }
         }
+
+        poison > 0 && self.damage(poison)
     }
 
-    pub fn draw(&self, res: &Resources) {
-        let position = res.physics.get_position(self.handle);
-        let rotation = res.physics.get_rotation(self.handle);
+    pub fn draw(&self, res: &Resources, world: &World) {
+        let position = self.get_position(world);
+        let rotation = world.physics.get_rotation(self.handle);
+        let data = self.variant.to_data();
+        let sprite = res
+            .assets
+            .enemies
+            .sprite(data.sprite.into())
+            .scale(data.scale);
         match self.status {
             Status::Alive { ref health, .. } => {
-                self.sprite
-                    .draw_tint(position, rotation, self.attack.enemy_tint());
+                let tint = self
+                    .boss
+                    .as_ref()
+                    .and_then(Boss::tint)
+                    .or_else(|| self.attack.enemy_tint())
+                    .or_else(|| self.afflictions.tint());
+                sprite.draw_tint(position, rotation, tint.unwrap_or(WHITE));
                 health.draw(position);
             }
             Status::Dead { fade_timer } => {
                 let alpha = fade_timer / FADE_TIME;
-                self.sprite.draw_alpha(position, rotation, alpha);
+                sprite.draw_alpha(position, rotation, alpha);
             }
         }
 
-        self.attack.draw(res);
+        self.attack.draw(res, world);
+        if let Some(boss) = &self.boss {
+            boss.draw(world);
+        }
     }
 }
 
 struct Attack {
     idx: GenerationalIndex,
-    sprite: Sprite,
     scale: f32,
 
     cooldown: f32,
@@ -263,46 +566,41 @@ enum AttackStatus {
 }
 
 impl Attack {
-    fn new(enemy_idx: GenerationalIndex, res: &mut Resources, scale: f32, cooldown: f32) -> Self {
+    fn new(enemy_idx: GenerationalIndex, scale: f32, cooldown: f32) -> Self {
         let idx = enemy_idx.with_group(groups::ENEMY_ATTACK);
 
-        let sprite = res.assets.enemies.sprite((7., 5.).into()).scale(scale);
-
         Attack {
             idx,
-            sprite,
             scale,
             cooldown,
             status: AttackStatus::Charging { timer: 0. },
         }
     }
 
-    fn enemy_tint(&self) -> Color {
+    fn enemy_tint(&self) -> Option<Color> {
         if let AttackStatus::PreAttack { timer, .. } = self.status {
             // flash red repeatedly
             let t = (timer % PRE_ATTACK_FLASH) * (1. / PRE_ATTACK_FLASH);
-            Color::new(1., t, t, 1.)
+            Some(Color::new(1., t, t, 1.))
         } else {
-            WHITE
+            None
         }
     }
 
-    fn remove(&self, res: &mut Resources) {
+    /// Whether the attack is winding up or under way
+    fn is_attacking(&self) -> bool {
+        !matches!(self.status, AttackStatus::Charging { .. })
+    }
+
+    fn remove(&self, world: &mut World) {
         if let AttackStatus::InProgress { handle, .. } = self.status {
-            res.physics.remove(handle);
+            world.physics.remove(handle);
         }
     }
 
-    fn update(
-        &mut self,
-        res: &mut Resources,
-        enemy_position: Vec2,
-        target: Option<&physics::Handle>,
-    ) {
-        let get_direction_to = |target| {
-            let target_position = res.physics.get_position(target);
-            (target_position - enemy_position).normalize_or_zero()
-        };
+    fn update(&mut self, world: &mut World, enemy_position: Vec2, target: Option<Vec2>) {
+        let get_direction_to =
+            |target_position: Vec2| (target_position - enemy_position).normalize_or_zero();
         let calc_position = |timer, direction| {
             let amount = timer / ATTACK_DURATION;
             let offset = lerp(ATTACK_OFFSET_START, ATTACK_OFFSET_END, amount) * direction;
@@ -312,12 +610,12 @@ impl Attack {
         match self.status {
             AttackStatus::Charging { ref mut timer } => {
                 // charge attack
-                *timer += res.delta;
+                *timer += world.delta;
                 if *timer < self.cooldown {
                     return;
                 }
 
-                if let Some(&target) = target {
+                if let Some(target) = target {
                     let direction = get_direction_to(target);
                     self.status = AttackStatus::PreAttack {
                         timer: 0.,
@@ -329,11 +627,11 @@ impl Attack {
                 ref mut timer,
                 direction,
             } => {
-                *timer += res.delta;
+                *timer += world.delta;
                 if *timer > PRE_ATTACK_DURATION {
-                    let collider = physics::ball(16. * self.scale).intersection_events();
+                    let collider = physics::ball(ATTACK_RADIUS * self.scale).intersection_events();
                     let position = calc_position(0., direction);
-                    let handle = res.physics.add_sensor(self.idx, collider, position);
+                    let handle = world.physics.add_sensor(self.idx, collider, position);
 
                     self.status = AttackStatus::InProgress {
                         timer: 0.,
@@ -347,33 +645,34 @@ impl Attack {
                 ref mut direction,
                 handle,
             } => {
-                *timer += res.delta;
+                *timer += world.delta;
                 if *timer > ATTACK_DURATION {
-                    res.physics.remove(handle);
+                    world.physics.remove(handle);
                     self.status = AttackStatus::Charging { timer: 0. };
                     return;
                 }
 
                 // if we have (still) a target, aim for it's updated position
-                if let Some(&target) = target {
+                if let Some(target) = target {
                     *direction = get_direction_to(target);
                 }
 
                 // move the sensor
                 let position = calc_position(*timer, *direction);
-                res.physics.set_position(handle, position);
+                world.physics.set_position(handle, position);
 
                 let angle = -direction.angle_between(Vec2::X);
-                res.physics.set_rotation(handle, angle);
+                world.physics.set_rotation(handle, angle);
             }
         }
     }
 
-    fn draw(&self, res: &Resources) {
+    fn draw(&self, res: &Resources, world: &World) {
         if let AttackStatus::InProgress { handle, .. } = self.status {
-            let position = res.physics.get_position(handle);
-            let rotation = res.physics.get_rotation(handle);
-            self.sprite.draw(position, rotation);
+            let position = world.physics.get_position(handle);
+            let rotation = world.physics.get_rotation(handle);
+            let sprite = res.assets.enemies.sprite((7., 5.).into()).scale(self.scale);
+            sprite.draw(position, rotation);
         }
     }
 }
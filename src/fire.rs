@@ -0,0 +1,71 @@
+//! What's left burning once some buildings are destroyed, see `buildings`.
+
+use macroquad::prelude::*;
+
+use crate::{entities::GenerationalIndex, simulation::World};
+
+/// Time between each round of damage
+const TICK: f32 = 1.;
+const FADE_TIME: f32 = 1.;
+const OUTER_COLOR: Color = Color::new(1., 0.4, 0.1, 0.3);
+const INNER_COLOR: Color = Color::new(1., 0.8, 0.2, 0.4);
+const FLICKER_SPEED: f32 = 12.;
+/// Relative to the radius
+const FLICKER_AMOUNT: f32 = 0.05;
+
+pub struct Fire {
+    idx: GenerationalIndex,
+    pub position: Vec2,
+    pub radius: f32,
+    /// Dealt to everything in range, every `TICK`
+    pub damage: u8,
+    /// Time left
+    timer: f32,
+    tick_timer: f32,
+}
+
+impl Fire {
+    pub fn new(
+        idx: GenerationalIndex,
+        position: Vec2,
+        radius: f32,
+        damage: u8,
+        duration: f32,
+    ) -> Self {
+        Fire {
+            idx,
+            position,
+            radius,
+            damage,
+            timer: duration,
+            tick_timer: 0.,
+        }
+    }
+
+    /// Returns whether or not the fire deals its damage this step
+    pub fn update(&mut self, world: &mut World) -> bool {
+        self.timer -= world.delta;
+        if self.timer < 0. {
+            world.deleted.push(self.idx);
+            return false;
+        }
+
+        self.tick_timer += world.delta;
+        if self.tick_timer >= TICK {
+            self.tick_timer -= TICK;
+            return true;
+        }
+        false
+    }
+
+    pub fn draw(&self) {
+        let fade = (self.timer / FADE_TIME).min(1.);
+        let flicker = 1. + FLICKER_AMOUNT * (self.timer * FLICKER_SPEED).sin();
+        let (x, y) = self.position.into();
+        for (color, scale) in [(OUTER_COLOR, flicker), (INNER_COLOR, 0.6 / flicker)] {
+            let mut color = color;
+            color.a *= fade;
+            draw_circle(x, y, self.radius * scale, color);
+        }
+    }
+}
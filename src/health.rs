@@ -26,6 +26,10 @@ impl Health {
         }
     }
 
+    pub fn fraction(&self) -> f32 {
+        self.health as f32 / self.max_health as f32
+    }
+
     pub fn is_empty(&self) -> bool {
         self.health == 0
     }
@@ -42,7 +46,7 @@ impl Health {
     }
 
     pub fn draw(&self, mut position: Vec2) {
-        let percent = self.health as f32 / self.max_health as f32;
+        let percent = self.fraction();
         let alpha = self.timer / FADE_TIME;
         position -= self.offset;
         let (x, y) = position.into();
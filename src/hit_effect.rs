@@ -1,7 +1,7 @@
 use macroquad::prelude::*;
 use std::f32::consts::PI;
 
-use crate::{entities::GenerationalIndex, Resources};
+use crate::{entities::GenerationalIndex, rng::Rng};
 
 // effect constants
 const NUM_PARTICLES: usize = 10;
@@ -33,15 +33,15 @@ struct Particle {
 }
 
 impl Particle {
-    fn new() -> Self {
+    fn new(rng: &mut Rng) -> Self {
         let origin = {
-            let angle = rand::gen_range(0., 1.0) * 2. * PI;
-            let radius = SPAWN_RADIUS * rand::gen_range(0., 1.0f32).sqrt();
+            let angle = rng.gen_range(0., 1.0) * 2. * PI;
+            let radius = SPAWN_RADIUS * rng.gen_range(0., 1.0f32).sqrt();
             vec2_from_polar(radius, angle)
         };
-        let size = rand::gen_range(SIZE.0, SIZE.1);
-        let life_timer = rand::gen_range(LIFETIME.0, LIFETIME.1);
-        let orbit = rand::gen_range(INITIAL_ORBIT.0, INITIAL_ORBIT.1);
+        let size = rng.gen_range(SIZE.0, SIZE.1);
+        let life_timer = rng.gen_range(LIFETIME.0, LIFETIME.1);
+        let orbit = rng.gen_range(INITIAL_ORBIT.0, INITIAL_ORBIT.1);
 
         Particle {
             origin,
@@ -54,30 +54,30 @@ impl Particle {
 }
 
 impl HitEffect {
-    pub fn new(idx: GenerationalIndex, origin: Vec2) -> Self {
+    pub fn new(idx: GenerationalIndex, rng: &mut Rng, origin: Vec2) -> Self {
         HitEffect {
             idx,
             origin,
             life_timer: EFFECT_LIFETIME,
             particles: [Particle {
                 life_timer: 0.,
-                ..Particle::new()
+                ..Particle::new(rng)
             }; NUM_PARTICLES],
         }
     }
 
-    pub fn update(&mut self, res: &mut Resources) {
-        self.life_timer -= res.delta;
+    pub fn update(&mut self, delta: f32, rng: &mut Rng, deleted: &mut Vec<GenerationalIndex>) {
+        self.life_timer -= delta;
         if self.life_timer < 0. {
-            res.deleted.push(self.idx);
+            deleted.push(self.idx);
             return;
         }
 
         for particle in &mut self.particles {
-            particle.life_timer -= res.delta;
+            particle.life_timer -= delta;
             if particle.life_timer < 0. {
                 // reset any particles that have finished
-                *particle = Particle::new();
+                *particle = Particle::new(rng);
             }
 
             particle.offset += LINEAR_SPEED;
@@ -0,0 +1,410 @@
+//! Maps each input action to the keys, mouse buttons and gamepad controls that trigger it.
+
+use std::fmt;
+
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::gamepad::{GamepadButton, Stick};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    MoveDeathball,
+    PanCamera,
+    Zoom,
+    ResetCamera,
+    Confirm,
+    Pause,
+    FastForward,
+    SaveReplay,
+    /// Only in debug builds, see `debug`
+    ToggleDebug,
+    DebugSpawn,
+    DebugStep,
+    /// Only in the editor, see `scenes::Editor`
+    EditorGrab,
+    EditorDelete,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Binding {
+    Key(#[serde(with = "KeyCodeDef")] KeyCode),
+    Mouse(#[serde(with = "MouseButtonDef")] MouseButton),
+    MouseWheel,
+    /// Combined into a single direction
+    Keys {
+        #[serde(with = "KeyCodeDef")]
+        up: KeyCode,
+        #[serde(with = "KeyCodeDef")]
+        left: KeyCode,
+        #[serde(with = "KeyCodeDef")]
+        down: KeyCode,
+        #[serde(with = "KeyCodeDef")]
+        right: KeyCode,
+    },
+    GamepadButton(GamepadButton),
+    /// Combined into a single amount, from -1 when only `negative` is held to 1 for `positive`
+    GamepadButtons {
+        negative: GamepadButton,
+        positive: GamepadButton,
+    },
+    GamepadStick(Stick),
+}
+
+/// Missing actions fall back to their default bindings, so that old saves keep working
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Bindings {
+    move_deathball: Vec<Binding>,
+    pan_camera: Vec<Binding>,
+    zoom: Vec<Binding>,
+    reset_camera: Vec<Binding>,
+    confirm: Vec<Binding>,
+    pause: Vec<Binding>,
+    fast_forward: Vec<Binding>,
+    save_replay: Vec<Binding>,
+    toggle_debug: Vec<Binding>,
+    debug_spawn: Vec<Binding>,
+    debug_step: Vec<Binding>,
+    editor_grab: Vec<Binding>,
+    editor_delete: Vec<Binding>,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Bindings {
+            move_deathball: vec![
+                Binding::Mouse(MouseButton::Left),
+                Binding::GamepadStick(Stick::Left),
+            ],
+            pan_camera: vec![
+                Binding::Mouse(MouseButton::Right),
+                Binding::Keys {
+                    up: KeyCode::W,
+                    left: KeyCode::A,
+                    down: KeyCode::S,
+                    right: KeyCode::D,
+                },
+                Binding::GamepadStick(Stick::Right),
+            ],
+            zoom: vec![
+                Binding::MouseWheel,
+                Binding::GamepadButtons {
+                    negative: GamepadButton::LeftTrigger,
+                    positive: GamepadButton::RightTrigger,
+                },
+            ],
+            reset_camera: vec![
+                Binding::Mouse(MouseButton::Middle),
+                Binding::GamepadButton(GamepadButton::RightStick),
+            ],
+            confirm: vec![
+                Binding::Key(KeyCode::Space),
+                Binding::GamepadButton(GamepadButton::South),
+            ],
+            pause: vec![
+                Binding::Key(KeyCode::Escape),
+                Binding::GamepadButton(GamepadButton::Start),
+            ],
+            fast_forward: vec![
+                Binding::Key(KeyCode::F),
+                Binding::GamepadButton(GamepadButton::North),
+            ],
+            save_replay: vec![Binding::Key(KeyCode::F5)],
+            toggle_debug: vec![Binding::Key(KeyCode::F1)],
+            debug_spawn: vec![Binding::Key(KeyCode::F2)],
+            debug_step: vec![Binding::Key(KeyCode::F3)],
+            editor_grab: vec![Binding::Mouse(MouseButton::Left)],
+            editor_delete: vec![
+                Binding::Key(KeyCode::Delete),
+                Binding::Key(KeyCode::Backspace),
+            ],
+        }
+    }
+}
+
+impl Bindings {
+    pub fn get(&self, action: Action) -> &[Binding] {
+        match action {
+            Action::MoveDeathball => &self.move_deathball,
+            Action::PanCamera => &self.pan_camera,
+            Action::Zoom => &self.zoom,
+            Action::ResetCamera => &self.reset_camera,
+            Action::Confirm => &self.confirm,
+            Action::Pause => &self.pause,
+            Action::FastForward => &self.fast_forward,
+            Action::SaveReplay => &self.save_replay,
+            Action::ToggleDebug => &self.toggle_debug,
+            Action::DebugSpawn => &self.debug_spawn,
+            Action::DebugStep => &self.debug_step,
+            Action::EditorGrab => &self.editor_grab,
+            Action::EditorDelete => &self.editor_delete,
+        }
+    }
+
+    /// Replaces the single button bindings of the action from the same kind of device (keyboard
+    /// and mouse, or gamepad), keeping any others (ie. sticks)
+    pub fn rebind(&mut self, action: Action, binding: Binding) {
+        let bindings = match action {
+            Action::MoveDeathball => &mut self.move_deathball,
+            Action::PanCamera => &mut self.pan_camera,
+            Action::Zoom => &mut self.zoom,
+            Action::ResetCamera => &mut self.reset_camera,
+            Action::Confirm => &mut self.confirm,
+            Action::Pause => &mut self.pause,
+            Action::FastForward => &mut self.fast_forward,
+            Action::SaveReplay => &mut self.save_replay,
+            Action::ToggleDebug => &mut self.toggle_debug,
+            Action::DebugSpawn => &mut self.debug_spawn,
+            Action::DebugStep => &mut self.debug_step,
+            Action::EditorGrab => &mut self.editor_grab,
+            Action::EditorDelete => &mut self.editor_delete,
+        };
+        bindings.retain(|other| !other.is_button() || other.is_gamepad() != binding.is_gamepad());
+        bindings.insert(0, binding);
+    }
+}
+
+impl Binding {
+    pub fn is_button(&self) -> bool {
+        matches!(
+            self,
+            Binding::Key(_) | Binding::Mouse(_) | Binding::GamepadButton(_)
+        )
+    }
+
+    pub fn is_gamepad(&self) -> bool {
+        matches!(
+            self,
+            Binding::GamepadButton(_) | Binding::GamepadButtons { .. } | Binding::GamepadStick(_)
+        )
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Binding::Key(key) => write!(f, "{:?}", key),
+            Binding::Mouse(button) => write!(f, "{:?} Mouse", button),
+            Binding::MouseWheel => write!(f, "Mouse Wheel"),
+            Binding::Keys {
+                up,
+                left,
+                down,
+                right,
+            } => write!(f, "{:?}/{:?}/{:?}/{:?}", up, left, down, right),
+            Binding::GamepadButton(button) => write!(f, "{:?} Button", button),
+            Binding::GamepadButtons { negative, positive } => {
+                write!(f, "{:?}/{:?} Buttons", negative, positive)
+            }
+            Binding::GamepadStick(stick) => write!(f, "{:?} Stick", stick),
+        }
+    }
+}
+
+// mirrors of the macroquad types, so that they can be (de)serialized
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "MouseButton")]
+enum MouseButtonDef {
+    Right,
+    Left,
+    Middle,
+    Unknown,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "KeyCode")]
+enum KeyCodeDef {
+    Space,
+    Apostrophe,
+    Comma,
+    Minus,
+    Period,
+    Slash,
+    Key0,
+    Key1,
+    Key2,
+    Key3,
+    Key4,
+    Key5,
+    Key6,
+    Key7,
+    Key8,
+    Key9,
+    Semicolon,
+    Equal,
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    I,
+    J,
+    K,
+    L,
+    M,
+    N,
+    O,
+    P,
+    Q,
+    R,
+    S,
+    T,
+    U,
+    V,
+    W,
+    X,
+    Y,
+    Z,
+    LeftBracket,
+    Backslash,
+    RightBracket,
+    GraveAccent,
+    World1,
+    World2,
+    Escape,
+    Enter,
+    Tab,
+    Backspace,
+    Insert,
+    Delete,
+    Right,
+    Left,
+    Down,
+    Up,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    CapsLock,
+    ScrollLock,
+    NumLock,
+    PrintScreen,
+    Pause,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    F13,
+    F14,
+    F15,
+    F16,
+    F17,
+    F18,
+    F19,
+    F20,
+    F21,
+    F22,
+    F23,
+    F24,
+    F25,
+    Kp0,
+    Kp1,
+    Kp2,
+    Kp3,
+    Kp4,
+    Kp5,
+    Kp6,
+    Kp7,
+    Kp8,
+    Kp9,
+    KpDecimal,
+    KpDivide,
+    KpMultiply,
+    KpSubtract,
+    KpAdd,
+    KpEnter,
+    KpEqual,
+    LeftShift,
+    LeftControl,
+    LeftAlt,
+    LeftSuper,
+    RightShift,
+    RightControl,
+    RightAlt,
+    RightSuper,
+    Menu,
+    Unknown,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_round_trip_through_ron() {
+        let bindings = Bindings::default();
+        let source = ron::to_string(&bindings).unwrap();
+        let parsed: Bindings = ron::from_str(&source).unwrap();
+        for action in [
+            Action::MoveDeathball,
+            Action::PanCamera,
+            Action::Zoom,
+            Action::ResetCamera,
+            Action::Confirm,
+            Action::Pause,
+        ] {
+            assert_eq!(parsed.get(action), bindings.get(action));
+        }
+    }
+
+    #[test]
+    fn should_default_missing_actions() {
+        let bindings: Bindings = ron::from_str("(confirm: [Key(Enter)])").unwrap();
+        assert_eq!(
+            bindings.get(Action::Confirm),
+            [Binding::Key(KeyCode::Enter)]
+        );
+        assert_eq!(
+            bindings.get(Action::Pause),
+            Bindings::default().get(Action::Pause)
+        );
+    }
+
+    #[test]
+    fn should_only_replace_button_bindings_when_rebinding() {
+        let mut bindings = Bindings::default();
+        bindings.rebind(Action::MoveDeathball, Binding::Key(KeyCode::LeftShift));
+        assert_eq!(
+            bindings.get(Action::MoveDeathball),
+            [
+                Binding::Key(KeyCode::LeftShift),
+                Binding::GamepadStick(Stick::Left)
+            ]
+        );
+    }
+
+    #[test]
+    fn should_keep_bindings_of_other_devices_when_rebinding() {
+        let mut bindings = Bindings::default();
+        bindings.rebind(Action::Pause, Binding::Key(KeyCode::P));
+        assert_eq!(
+            bindings.get(Action::Pause),
+            [
+                Binding::Key(KeyCode::P),
+                Binding::GamepadButton(GamepadButton::Start)
+            ]
+        );
+
+        bindings.rebind(Action::Pause, Binding::GamepadButton(GamepadButton::Select));
+        assert_eq!(
+            bindings.get(Action::Pause),
+            [
+                Binding::GamepadButton(GamepadButton::Select),
+                Binding::Key(KeyCode::P)
+            ]
+        );
+    }
+}
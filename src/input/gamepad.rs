@@ -0,0 +1,174 @@
+//! The first connected gamepad, laid out like the standard mapping of the web Gamepad API.
+//!
+//! Gamepads are only read on the web for now, natively there's no backend so nothing is ever
+//! pressed, and gamepad bindings are left out of the controls list.
+
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Whether gamepads can be read at all on this platform
+pub const IS_SUPPORTED: bool = cfg!(target_arch = "wasm32");
+
+const BUTTONS: usize = 16;
+const AXES: usize = 4;
+
+const BUTTON_THRESHOLD: f32 = 0.5;
+const STICK_DEADZONE: f32 = 0.2;
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum GamepadButton {
+    South,
+    East,
+    West,
+    North,
+    LeftBumper,
+    RightBumper,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    LeftStick,
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+const ALL_BUTTONS: [GamepadButton; BUTTONS] = {
+    use GamepadButton::*;
+    [
+        South,
+        East,
+        West,
+        North,
+        LeftBumper,
+        RightBumper,
+        LeftTrigger,
+        RightTrigger,
+        Select,
+        Start,
+        LeftStick,
+        RightStick,
+        DPadUp,
+        DPadDown,
+        DPadLeft,
+        DPadRight,
+    ]
+};
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Stick {
+    Left,
+    Right,
+}
+
+pub struct Gamepad {
+    buttons: [bool; BUTTONS],
+    previous: [bool; BUTTONS],
+    axes: [f32; AXES],
+}
+
+impl Gamepad {
+    pub fn new() -> Self {
+        Gamepad {
+            buttons: [false; BUTTONS],
+            previous: [false; BUTTONS],
+            axes: [0.; AXES],
+        }
+    }
+
+    pub fn update(&mut self) {
+        self.set_state(&backend::poll());
+    }
+
+    /// Axes first then buttons, or empty if there's no gamepad
+    fn set_state(&mut self, state: &[f32]) {
+        self.previous = self.buttons;
+        for (i, axis) in self.axes.iter_mut().enumerate() {
+            *axis = state.get(i).copied().unwrap_or(0.);
+        }
+        for (i, button) in self.buttons.iter_mut().enumerate() {
+            *button = state.get(AXES + i).copied().unwrap_or(0.) > BUTTON_THRESHOLD;
+        }
+    }
+
+    pub fn is_down(&self, button: GamepadButton) -> bool {
+        self.buttons[button as usize]
+    }
+
+    pub fn is_pressed(&self, button: GamepadButton) -> bool {
+        self.buttons[button as usize] && !self.previous[button as usize]
+    }
+
+    pub fn last_pressed(&self) -> Option<GamepadButton> {
+        ALL_BUTTONS
+            .into_iter()
+            .find(|&button| self.is_pressed(button))
+    }
+
+    /// Scaled so that it starts from zero at the edge of the deadzone
+    pub fn stick(&self, stick: Stick) -> Vec2 {
+        let value = match stick {
+            Stick::Left => vec2(self.axes[0], self.axes[1]),
+            Stick::Right => vec2(self.axes[2], self.axes[3]),
+        };
+        let length = value.length();
+        if length < STICK_DEADZONE {
+            Vec2::ZERO
+        } else {
+            let scaled = ((length - STICK_DEADZONE) / (1. - STICK_DEADZONE)).min(1.);
+            value / length * scaled
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod backend {
+    pub fn poll() -> Vec<f32> {
+        Vec::new()
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod backend {
+    // see index.html
+    #[wasm_bindgen::prelude::wasm_bindgen]
+    extern "C" {
+        fn poll_gamepad() -> Vec<f32>;
+    }
+
+    pub fn poll() -> Vec<f32> {
+        poll_gamepad()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(pressed: &[GamepadButton]) -> Vec<f32> {
+        let mut state = vec![0.; AXES + BUTTONS];
+        for &button in pressed {
+            state[AXES + button as usize] = 1.;
+        }
+        state
+    }
+
+    #[test]
+    fn should_only_count_a_held_button_as_pressed_once() {
+        let mut gamepad = Gamepad::new();
+        let mut toggles = 0;
+        for pressed in [
+            &[][..],
+            &[GamepadButton::Start],
+            &[GamepadButton::Start],
+            &[],
+        ] {
+            gamepad.set_state(&state(pressed));
+            toggles += gamepad.is_pressed(GamepadButton::Start) as usize;
+        }
+        assert_eq!(toggles, 1);
+        assert_eq!(gamepad.last_pressed(), None);
+    }
+}
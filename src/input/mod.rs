@@ -0,0 +1,263 @@
+use macroquad::prelude::*;
+
+use crate::save::Settings;
+
+pub mod bindings;
+pub mod gamepad;
+
+use bindings::{Action, Binding, Bindings};
+use gamepad::Gamepad;
+
+/// How far holding down a zoom button zooms every frame, relative to a step of the mouse wheel
+const GAMEPAD_ZOOM_SPEED: f32 = 0.25;
+
+pub struct Input {
+    bindings: Bindings,
+    gamepad: Gamepad,
+    drag: Option<MouseDrag>,
+    pan_speed: f32,
+    zoom_sensitivity: f32,
+}
+
+#[derive(Clone, Copy)]
+pub struct MouseDrag {
+    pub previous: Vec2,
+    pub current: Vec2,
+}
+
+impl Input {
+    pub fn new(settings: &Settings) -> Self {
+        Input {
+            bindings: settings.bindings.clone(),
+            gamepad: Gamepad::new(),
+            drag: None,
+            pan_speed: settings.pan_speed,
+            zoom_sensitivity: settings.zoom_sensitivity,
+        }
+    }
+
+    pub fn apply_settings(&mut self, settings: &Settings) {
+        self.bindings = settings.bindings.clone();
+        self.pan_speed = settings.pan_speed;
+        self.zoom_sensitivity = settings.zoom_sensitivity;
+    }
+
+    pub fn update(&mut self) {
+        self.gamepad.update();
+
+        // dragging with any of the mouse buttons bound to panning
+        let is_down = self
+            .bindings
+            .get(Action::PanCamera)
+            .iter()
+            .any(|binding| matches!(binding, Binding::Mouse(_)) && self.is_down(binding));
+        match self.drag {
+            // start drag
+            None if is_down => {
+                let position = mouse_position().into();
+                self.drag = Some(MouseDrag {
+                    previous: position,
+                    current: position,
+                });
+            }
+            // continue drag
+            Some(ref mut drag) if is_down => {
+                drag.previous = drag.current;
+                drag.current = mouse_position().into();
+            }
+            // end drag
+            Some(_) if !is_down => self.drag = None,
+            _ => {}
+        }
+    }
+
+    fn is_down(&self, binding: &Binding) -> bool {
+        match *binding {
+            Binding::Key(key) => is_key_down(key),
+            Binding::Mouse(button) => is_mouse_button_down(button),
+            Binding::GamepadButton(button) => self.gamepad.is_down(button),
+            _ => false,
+        }
+    }
+
+    fn is_pressed(&self, binding: &Binding) -> bool {
+        match *binding {
+            Binding::Key(key) => is_key_pressed(key),
+            Binding::Mouse(button) => is_mouse_button_pressed(button),
+            Binding::GamepadButton(button) => self.gamepad.is_pressed(button),
+            _ => false,
+        }
+    }
+
+    fn direction(&self, binding: &Binding) -> Vec2 {
+        match *binding {
+            Binding::Keys {
+                up,
+                left,
+                down,
+                right,
+            } => {
+                let mut delta = vec2(0., 0.);
+                if is_key_down(up) {
+                    delta.y -= 1.0;
+                }
+                if is_key_down(left) {
+                    delta.x -= 1.0;
+                }
+                if is_key_down(down) {
+                    delta.y += 1.0;
+                }
+                if is_key_down(right) {
+                    delta.x += 1.0;
+                }
+                delta.normalize_or_zero()
+            }
+            Binding::GamepadStick(stick) => self.gamepad.stick(stick),
+            _ => Vec2::ZERO,
+        }
+    }
+
+    fn amount(&self, binding: &Binding) -> f32 {
+        match *binding {
+            Binding::MouseWheel => {
+                let value = mouse_wheel().1;
+                if cfg!(target_arch = "wasm32") {
+                    value.clamp(-1., 1.)
+                } else {
+                    value.clamp(-2., 2.)
+                }
+            }
+            Binding::GamepadButtons { negative, positive } => {
+                let mut amount = 0.;
+                if self.gamepad.is_down(negative) {
+                    amount -= GAMEPAD_ZOOM_SPEED;
+                }
+                if self.gamepad.is_down(positive) {
+                    amount += GAMEPAD_ZOOM_SPEED;
+                }
+                amount
+            }
+            _ => 0.,
+        }
+    }
+
+    fn is_action_down(&self, action: Action) -> bool {
+        let bindings = self.bindings.get(action);
+        bindings.iter().any(|binding| self.is_down(binding))
+    }
+
+    fn is_action_pressed(&self, action: Action) -> bool {
+        let bindings = self.bindings.get(action);
+        bindings.iter().any(|binding| self.is_pressed(binding))
+    }
+
+    /// Combined from every binding of the action, at most a length of 1
+    fn action_direction(&self, action: Action) -> Vec2 {
+        let bindings = self.bindings.get(action);
+        let direction = bindings.iter().fold(Vec2::ZERO, |direction, binding| {
+            direction + self.direction(binding)
+        });
+        direction.clamp_length_max(1.)
+    }
+
+    /// The next key, mouse button or gamepad button that's pressed, for rebinding actions
+    pub fn last_pressed(&self) -> Option<Binding> {
+        if let Some(key) = get_last_key_pressed() {
+            return Some(Binding::Key(key));
+        }
+        for button in [MouseButton::Left, MouseButton::Right, MouseButton::Middle] {
+            if is_mouse_button_pressed(button) {
+                return Some(Binding::Mouse(button));
+            }
+        }
+        self.gamepad.last_pressed().map(Binding::GamepadButton)
+    }
+
+    pub fn go_to_next_scene(&self) -> bool {
+        self.is_action_down(Action::Confirm)
+    }
+
+    /// Towards the cursor, in screen coordinates
+    pub fn move_deathball(&self) -> Option<Vec2> {
+        if self.is_action_down(Action::MoveDeathball) {
+            Some(mouse_position().into())
+        } else {
+            None
+        }
+    }
+
+    /// For moving the deathball with a stick (or keys) instead of the cursor
+    pub fn steer_deathball(&self) -> Vec2 {
+        self.action_direction(Action::MoveDeathball)
+    }
+
+    pub fn pan_camera(&self) -> Vec2 {
+        self.action_direction(Action::PanCamera) * self.pan_speed
+    }
+
+    pub fn pan_camera_mouse_drag(&self) -> Option<MouseDrag> {
+        self.drag
+    }
+
+    pub fn pause(&self) -> bool {
+        self.is_action_pressed(Action::Pause)
+    }
+
+    /// Toggles it, rather than needing to be held down
+    pub fn fast_forward(&self) -> bool {
+        self.is_action_pressed(Action::FastForward)
+    }
+
+    pub fn toggle_debug(&self) -> bool {
+        self.is_action_pressed(Action::ToggleDebug)
+    }
+
+    pub fn debug_spawn(&self) -> bool {
+        self.is_action_pressed(Action::DebugSpawn)
+    }
+
+    pub fn debug_step(&self) -> bool {
+        self.is_action_pressed(Action::DebugStep)
+    }
+
+    /// Where the cursor is when it's first pressed, in screen coordinates
+    pub fn editor_grab(&self) -> Option<Vec2> {
+        if self.is_action_pressed(Action::EditorGrab) {
+            Some(mouse_position().into())
+        } else {
+            None
+        }
+    }
+
+    /// Where the cursor is for as long as it's held after being pressed, in screen coordinates
+    pub fn editor_drag(&self) -> Option<Vec2> {
+        if self.is_action_down(Action::EditorGrab) {
+            Some(mouse_position().into())
+        } else {
+            None
+        }
+    }
+
+    pub fn editor_delete(&self) -> bool {
+        self.is_action_pressed(Action::EditorDelete)
+    }
+
+    /// Replays are saved to files, so this is never true on the web
+    pub fn save_replay(&self) -> bool {
+        !cfg!(target_arch = "wasm32") && self.is_action_pressed(Action::SaveReplay)
+    }
+
+    pub fn reset_camera(&self) -> bool {
+        self.is_action_down(Action::ResetCamera)
+    }
+
+    pub fn zoom_camera(&self) -> Option<f32> {
+        let bindings = self.bindings.get(Action::Zoom);
+        let value: f32 = bindings.iter().map(|binding| self.amount(binding)).sum();
+        if value == 0.0 {
+            None
+        } else {
+            Some(value * self.zoom_sensitivity)
+        }
+    }
+}
@@ -0,0 +1,275 @@
+//! Serializable description of a level, see the files in `assets/levels` for examples.
+//!
+//! Entities are listed in spawn order as `(Variant, (x, y))` pairs.
+
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    animals::Variant as AnimalVariant,
+    audio::bgm,
+    background::{Background, Prop},
+    buildings::Variant as BuildingVariant,
+    drops::DropRules,
+    enemies::Variant as EnemyVariant,
+    objectives::Objective,
+    spawners::Wave,
+    text_bubbles::TextBubble,
+};
+
+/// Kept around every entity when working out a level's bounds, so none are right at the edge
+const BOUNDS_MARGIN: f32 = 256.;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LevelDefinition {
+    pub bgm: bgm::Track,
+    pub max_score: u32,
+    pub objective: Objective,
+    pub background: BackgroundDefinition,
+    #[serde(default)]
+    pub text_bubbles: Vec<TextBubbleDefinition>,
+    #[serde(default)]
+    pub animals: Vec<(AnimalVariant, (f32, f32))>,
+    #[serde(default)]
+    pub buildings: Vec<(BuildingVariant, (f32, f32))>,
+    #[serde(default)]
+    pub enemies: Vec<(EnemyVariant, (f32, f32))>,
+    /// Buildings that objectives can ask for by tag, as `(tag, Variant, (x, y))`
+    #[serde(default)]
+    pub tagged_buildings: Vec<(String, BuildingVariant, (f32, f32))>,
+    /// Overrides what destroyed buildings drop, see `drops`
+    #[serde(default)]
+    pub drops: DropRules,
+    #[serde(default)]
+    pub spawners: Vec<SpawnerDefinition>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BackgroundDefinition {
+    pub clear_color: (f32, f32, f32, f32),
+    pub offset: (f32, f32),
+    #[serde(default)]
+    pub props: Vec<((u32, u32), Prop)>,
+}
+
+/// Sends in enemies over the course of the level, on top of those placed up front
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SpawnerDefinition {
+    pub position: (f32, f32),
+    pub waves: Vec<Wave>,
+    /// Keeps repeating the last wave, with more enemies each time
+    #[serde(default)]
+    pub endless: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TextBubbleDefinition {
+    pub position: (f32, f32),
+    pub rows: Vec<String>,
+}
+
+impl LevelDefinition {
+    pub fn parse(source: &str) -> ron::Result<Self> {
+        ron::from_str(source)
+    }
+
+    /// For tests, a level with just the objective and entities given as RON (eg. `"enemies: []"`).
+    /// Unless they include animals there's a horse for the death ball to start with, just off the
+    /// origin where the death ball starts (exactly on it, there'd be no direction to pull it in).
+    #[cfg(test)]
+    pub fn for_test(objective: &str, entities: &str) -> Self {
+        let source = format!(
+            "(bgm: MeadowMeadow, max_score: 0, objective: ({}), \
+              background: (clear_color: (0., 0., 0., 1.), offset: (0., 0.)), {})",
+            objective, entities
+        );
+        let mut definition = LevelDefinition::parse(&source).unwrap();
+        if definition.animals.is_empty() {
+            definition
+                .animals
+                .push((AnimalVariant::Horse, (0.00001, 0.)));
+        }
+        definition
+    }
+
+    pub fn to_ron(&self) -> String {
+        let config = ron::ser::PrettyConfig::new()
+            .depth_limit(3)
+            .indentor("    ".to_owned());
+        ron::ser::to_string_pretty(self, config).unwrap()
+    }
+
+    pub fn background(&self) -> Background {
+        let (r, g, b, a) = self.background.clear_color;
+        Background::new(
+            Color::new(r, g, b, a),
+            self.background.offset.into(),
+            self.background.props.clone(),
+        )
+    }
+
+    /// Everything the camera can be moved to, as the background's props don't always cover
+    /// every entity (or where the deathball starts, at the origin)
+    pub fn bounds(&self) -> Rect {
+        let animals = self.animals.iter().map(|(_, position)| *position);
+        let buildings = self.buildings.iter().map(|(_, position)| *position);
+        let enemies = self.enemies.iter().map(|(_, position)| *position);
+        let tagged = self
+            .tagged_buildings
+            .iter()
+            .map(|(_, _, position)| *position);
+        let spawners = self.spawners.iter().map(|spawner| spawner.position);
+        let size = BOUNDS_MARGIN * 2.;
+        let entities = animals
+            .chain(buildings)
+            .chain(enemies)
+            .chain(tagged)
+            .chain(spawners)
+            .map(|(x, y)| Rect::new(x - BOUNDS_MARGIN, y - BOUNDS_MARGIN, size, size))
+            .fold(
+                Rect::new(-BOUNDS_MARGIN, -BOUNDS_MARGIN, size, size),
+                Rect::combine_with,
+            );
+
+        match self.background().bounds() {
+            Some(background) => background.combine_with(entities),
+            None => entities,
+        }
+    }
+
+    pub fn text_bubbles(&self) -> Vec<TextBubble> {
+        self.text_bubbles
+            .iter()
+            .map(|bubble| TextBubble::new(bubble.position.into(), bubble.rows.clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> LevelDefinition {
+        LevelDefinition::parse(source).unwrap()
+    }
+
+    #[test]
+    fn should_parse_the_tutorial_scenario() {
+        let level = parse(include_str!("../../assets/levels/tutorial_scenario.ron"));
+        assert_eq!(level.max_score, 30000);
+        assert_eq!(level.background.props.len(), 1110);
+        assert_eq!(level.text_bubbles.len(), 5);
+        assert_eq!(level.animals.len(), 4);
+        assert_eq!(level.buildings.len(), 69);
+        assert_eq!(level.enemies.len(), 1);
+    }
+
+    #[test]
+    fn should_parse_scenario_1() {
+        let level = parse(include_str!("../../assets/levels/scenario_1.ron"));
+        assert_eq!(level.max_score, 30000);
+        assert_eq!(level.background.props.len(), 1946);
+        assert_eq!(level.animals.len(), 4);
+        assert_eq!(level.buildings.len(), 104);
+        assert_eq!(level.enemies.len(), 24);
+    }
+
+    #[test]
+    fn should_parse_scenario_2() {
+        let level = parse(include_str!("../../assets/levels/scenario_2.ron"));
+        assert_eq!(level.max_score, 30000);
+        assert_eq!(level.background.props.len(), 10258);
+        assert_eq!(level.animals.len(), 5);
+        assert_eq!(level.buildings.len(), 288);
+        assert_eq!(level.enemies.len(), 31);
+    }
+
+    #[test]
+    fn should_parse_the_final_scenario() {
+        let level = parse(include_str!("../../assets/levels/final_scenario.ron"));
+        assert_eq!(level.max_score, 60000);
+        assert_eq!(level.background.props.len(), 1583);
+        assert_eq!(level.animals.len(), 6);
+        assert_eq!(level.buildings.len(), 85);
+        assert_eq!(level.enemies.len(), 5);
+    }
+
+    #[test]
+    fn should_parse_the_endless_level() {
+        let level = parse(include_str!("../../assets/levels/endless.ron"));
+        assert_eq!(level.spawners.len(), 2);
+        assert!(level.spawners.iter().all(|spawner| spawner.endless));
+    }
+
+    #[test]
+    fn should_default_missing_entity_lists_to_empty() {
+        let level = parse(
+            "(
+                bgm: Space,
+                max_score: 0,
+                objective: (kind: None, target: 1),
+                background: (clear_color: (0.0, 0.0, 0.0, 1.0), offset: (0.0, 0.0)),
+            )",
+        );
+        assert!(level.background.props.is_empty());
+        assert!(level.text_bubbles.is_empty());
+        assert!(level.animals.is_empty());
+        assert!(level.buildings.is_empty());
+        assert!(level.enemies.is_empty());
+        assert!(level.spawners.is_empty());
+    }
+
+    #[test]
+    fn should_parse_drop_overrides() {
+        let level = parse(
+            "(
+                bgm: Space,
+                max_score: 0,
+                objective: (kind: None, target: 1),
+                background: (clear_color: (0.0, 0.0, 0.0, 1.0), offset: (0.0, 0.0)),
+                drops: (
+                    tables: { Barn: [(Horse, 3), (Kuma, 1)] },
+                    caps: { Kuma: 2 },
+                ),
+            )",
+        );
+        assert_eq!(level.drops.tables[&BuildingVariant::Barn].len(), 2);
+        assert_eq!(level.drops.caps[&AnimalVariant::Kuma], 2);
+    }
+
+    #[test]
+    fn should_keep_every_entity_within_the_bounds() {
+        for (level, path) in crate::levels::LEVEL_FILES {
+            let definition = parse(&std::fs::read_to_string(path).unwrap());
+            let bounds = definition.bounds();
+
+            let animals = definition.animals.iter().map(|(_, position)| *position);
+            let buildings = definition.buildings.iter().map(|(_, position)| *position);
+            let enemies = definition.enemies.iter().map(|(_, position)| *position);
+            let spawners = definition.spawners.iter().map(|spawner| spawner.position);
+            let positions = animals
+                .chain(buildings)
+                .chain(enemies)
+                .chain(spawners)
+                .chain([(0., 0.)]);
+            for position in positions {
+                assert!(
+                    bounds.contains(position.into()),
+                    "{:?} at {:?} is outside of {:?}",
+                    level,
+                    position,
+                    bounds
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn should_round_trip_through_ron() {
+        let level = parse(include_str!("../../assets/levels/tutorial_scenario.ron"));
+        let source = ron::to_string(&level).unwrap();
+        let reparsed = parse(&source);
+        assert_eq!(reparsed.buildings.len(), level.buildings.len());
+        assert_eq!(reparsed.text_bubbles[0].rows, level.text_bubbles[0].rows);
+    }
+}
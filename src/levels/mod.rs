@@ -1,49 +1,45 @@
-use crate::{
-    animals::Animal, audio::bgm, background::Background, buildings::Building, enemies::Enemy,
-    entities::Entities, groups, objectives::Objective, text_bubbles::TextBubble, Resources,
-};
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, Eq, PartialEq, Hash)]
+use crate::{assets::Assets, rng::Rng};
+
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
 pub enum Level {
     Test,
     Tutorial,
     Scenario1,
     Scenario2,
     Final,
+    Endless,
+    /// Made in the editor, which hands its definition straight to `Combat`
+    Custom,
 }
 
 pub use Level::*;
 
 impl Level {
-    pub fn init(&self, res: &mut Resources) -> LevelData {
+    /// None for custom levels, which are only kept by the editor
+    pub fn definition(&self, assets: &Assets, rng: &mut Rng) -> Option<LevelDefinition> {
         match self {
-            Test => test::init(res),
-            Tutorial => tutorial_scenario::init(res),
-            Scenario1 => scenario_1::init(res),
-            Scenario2 => scenario_2::init(res),
-            Final => final_scenario::init(res),
+            Test => Some(test::definition(rng)),
+            Custom => None,
+            _ => Some(assets.levels[self].clone()),
         }
     }
 }
 
-pub struct LevelData {
-    pub bgm: bgm::Track,
-    pub max_score: u32,
-    pub objective: Objective,
-    pub background: Background,
-    pub text_bubbles: Vec<TextBubble>,
-    pub animals: Entities<Animal, { groups::ANIMAL }>,
-    pub buildings: Entities<Building, { groups::BUILDING }>,
-    pub enemies: Entities<Enemy, { groups::ENEMY }>,
-}
-
-// individual levels
-mod test;
-
-mod tutorial_scenario;
+/// Levels that are defined by a level file, along with the path of said file
+pub const LEVEL_FILES: [(Level, &str); 5] = [
+    (Tutorial, "./assets/levels/tutorial_scenario.ron"),
+    (Scenario1, "./assets/levels/scenario_1.ron"),
+    (Scenario2, "./assets/levels/scenario_2.ron"),
+    (Final, "./assets/levels/final_scenario.ron"),
+    (Endless, "./assets/levels/endless.ron"),
+];
 
-mod scenario_1;
+mod definition;
+pub use definition::{BackgroundDefinition, LevelDefinition, TextBubbleDefinition};
 
-mod scenario_2;
+pub mod tscn;
 
-mod final_scenario;
+// individual levels
+mod test;
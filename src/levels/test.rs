@@ -1,24 +1,19 @@
-use macroquad::prelude::*;
-
 use crate::{
-    animals::Animal,
+    animals::Variant as AnimalVariant,
     audio::bgm,
-    background::{Background, Prop::*},
-    buildings::{Building, Variant::*},
-    enemies::{Enemy, Variant::*},
-    entities::Entities,
-    levels::LevelData,
+    background::Prop::*,
+    buildings::Variant::*,
+    enemies::Variant::*,
+    levels::{definition::BackgroundDefinition, LevelDefinition},
     objectives::Objective,
-    Resources,
+    rng::Rng,
 };
 
-pub fn init(res: &mut Resources) -> LevelData {
-    let objective = Objective::none();
-
-    let background = Background::new(
-        Color::new(59. / 255., 99. / 255., 38. / 255., 1.),
-        vec2(0., 0.),
-        vec![
+pub fn definition(rng: &mut Rng) -> LevelDefinition {
+    let background = BackgroundDefinition {
+        clear_color: (59. / 255., 99. / 255., 38. / 255., 1.),
+        offset: (0., 0.),
+        props: vec![
             ((2, 2), Grass1),
             ((3, 2), Grass2),
             ((4, 2), Grass3),
@@ -33,53 +28,55 @@ pub fn init(res: &mut Resources) -> LevelData {
             ((8, 2), Mud),
             ((8, 3), Hay),
         ],
-    );
+    };
 
-    let mut animals = Entities::new();
-    let mut buildings = Entities::new();
-    let mut enemies = Entities::new();
+    let mut buildings = Vec::new();
 
     for pos in [
-        vec2(0., -500.),
-        vec2(-344., -500.),
-        vec2(344., -500.),
-        vec2(0., 500.),
-        vec2(-344., 500.),
-        vec2(344., 500.),
+        (0., -500.),
+        (-344., -500.),
+        (344., -500.),
+        (0., 500.),
+        (-344., 500.),
+        (344., 500.),
     ] {
-        buildings.push(|idx| Building::new(FenceH, idx, res, pos));
+        buildings.push((FenceH, pos));
     }
 
     for pos in [
-        vec2(-530., -344.),
-        vec2(-530., 0.),
-        vec2(-530., 344.),
-        vec2(530., -344.),
-        vec2(530., 0.),
-        vec2(530., 344.),
+        (-530., -344.),
+        (-530., 0.),
+        (-530., 344.),
+        (530., -344.),
+        (530., 0.),
+        (530., 344.),
     ] {
-        buildings.push(|idx| Building::new(FenceV, idx, res, pos));
+        buildings.push((FenceV, pos));
     }
 
-    buildings.push(|idx| Building::new(Barn, idx, res, vec2(0., 0.)));
+    buildings.push((Barn, (0., 0.)));
 
-    enemies.push(|idx| Enemy::new(Demon, idx, res, vec2(0., 100.)));
+    let enemies = vec![(Demon, (0., 100.))];
 
+    let mut animals = Vec::new();
     for _ in 0..10 {
-        let x = rand::gen_range(-450., 450.);
-        let y = rand::gen_range(-450., 450.);
+        let x = rng.gen_range(-450., 450.);
+        let y = rng.gen_range(-450., 450.);
 
-        animals.push(|idx| Animal::random(idx, res, vec2(x, y)));
+        animals.push((AnimalVariant::random(rng), (x, y)));
     }
 
-    LevelData {
+    LevelDefinition {
         bgm: bgm::MeadowMeadow,
         max_score: 0,
-        objective,
+        objective: Objective::none(),
         background,
+        text_bubbles: vec![],
         animals,
         buildings,
         enemies,
-        text_bubbles: vec![],
+        tagged_buildings: vec![],
+        drops: Default::default(),
+        spawners: vec![],
     }
 }
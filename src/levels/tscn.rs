@@ -0,0 +1,544 @@
+//! Imports scenarios from the original game's Godot scene (`.tscn`) files.
+//!
+//! Usage: `deathball import-tscn <file.tscn>`, the level file is printed to stdout.
+
+use std::collections::HashMap;
+
+use crate::{
+    animals::Variant as AnimalVariant,
+    audio::bgm,
+    background::Prop,
+    buildings::Variant as BuildingVariant,
+    enemies::Variant as EnemyVariant,
+    levels::definition::{BackgroundDefinition, LevelDefinition, TextBubbleDefinition},
+    objectives::Objective,
+};
+
+const DEFAULT_BG_COLOR: (f32, f32, f32, f32) = (0.23, 0.39, 0.15, 1.);
+const DEFAULT_SCORE: u32 = 300;
+const TILE_SIZE: f32 = 32.;
+
+type Datum = HashMap<String, String>;
+
+/// Entry point for the `import-tscn` subcommand
+pub fn run(path: &str) {
+    let result = std::fs::read_to_string(path)
+        .map_err(|err| err.to_string())
+        .and_then(|source| import(&source));
+    match result {
+        Ok(level) => println!("{}", level.to_ron()),
+        Err(err) => {
+            eprintln!("Failed to import {}: {}", path, err);
+            std::process::exit(1);
+        }
+    }
+}
+
+pub fn import(source: &str) -> Result<LevelDefinition, String> {
+    let data = parse(source);
+
+    let find = |key: &str, value: &str| {
+        data.iter()
+            .find(|datum| datum.get(key).map(String::as_str) == Some(value))
+    };
+
+    // objective, made once the enemies are known
+    let (objective_kind, objective_target, max_score) = {
+        let datum = data
+            .iter()
+            .find(|datum| datum.contains_key("objective"))
+            .ok_or("Missing objective")?;
+        let kind = parse_int(&datum["objective"])?;
+        let target = parse_int(
+            datum
+                .get("objective_count")
+                .ok_or("Missing objective_count")?,
+        )?;
+        let score = match datum.get("score") {
+            Some(score) => parse_int(score)? as u32,
+            None => DEFAULT_SCORE,
+        };
+        (kind, target as u16, score * 100)
+    };
+
+    // scenario opts
+    let clear_color = {
+        let id = &find("path", "res://scenarios/base_scenario.tscn")
+            .ok_or("Missing base scenario")?["id"];
+        let scenario = find("instance", &format!("ExtResource({})", id))
+            .ok_or("Missing base scenario instance")?;
+        match scenario.get("background_color") {
+            Some(color) => parse_color(color)?,
+            None => DEFAULT_BG_COLOR,
+        }
+    };
+
+    // props
+    let (offset, props) = {
+        let tilemap = data
+            .iter()
+            .find(|datum| datum.contains_key("tile_data"))
+            .ok_or("Missing tilemap")?;
+        let tiles = tilemap["tile_data"]
+            .trim_start_matches("PoolIntArray(")
+            .trim_end_matches(')')
+            .split(',')
+            .map(|tile| parse_int(tile.trim()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut props = Vec::new();
+        for tile in tiles.chunks(3) {
+            let position = index_to_xy(tile[0]);
+            let tile_idx = *tile.get(1).ok_or("Truncated tile data")?;
+            match tile_to_prop(tile_idx) {
+                Ok(Some(prop)) => props.push((position, prop)),
+                Ok(None) => {}
+                Err(()) => return Err(format!("Unknown prop: \"{}\"", tile_idx)),
+            }
+        }
+
+        let min_x = props.iter().map(|((x, _), _)| *x).fold(0, i32::min);
+        let min_y = props.iter().map(|((_, y), _)| *y).fold(0, i32::min);
+        let props = props
+            .into_iter()
+            .map(|((x, y), prop)| (((x - min_x) as u32, (y - min_y) as u32), prop))
+            .collect();
+        let offset = (min_x as f32 * TILE_SIZE, min_y as f32 * TILE_SIZE);
+        (offset, props)
+    };
+
+    // buildings
+    let buildings = instances(&data, "res://entities/buildings/", |path| {
+        let name = path
+            .trim_start_matches("res://entities/buildings/128/")
+            .trim_end_matches("_128.tscn");
+        building_variant(name).ok_or_else(|| format!("Unknown building: {}", path))
+    })?
+    .into_iter()
+    .map(|(variant, datum)| {
+        let position = datum
+            .get("position")
+            .ok_or("Building is missing a position")?;
+        Ok((variant, parse_vector2(position)?))
+    })
+    .collect::<Result<Vec<_>, String>>()?;
+
+    // animals
+    let animals = instances(&data, "res://entities/player/", |path| {
+        let name = path
+            .trim_start_matches("res://entities/player/")
+            .trim_end_matches(".tscn");
+        animal_variant(name).ok_or_else(|| format!("Unknown animal: {}", path))
+    })?
+    .into_iter()
+    .map(|(variant, datum)| {
+        let mut position = optional_position(datum)?;
+        // avoid placing an animal exactly on the origin
+        if position == (0., 0.) {
+            position.0 = 0.00001;
+        }
+        Ok((variant, position))
+    })
+    .collect::<Result<Vec<_>, String>>()?;
+
+    // enemies
+    let enemies = instances(&data, "res://entities/enemies/", |path| {
+        let name = path
+            .trim_start_matches("res://entities/enemies/")
+            .trim_end_matches(".tscn");
+        enemy_variant(name).ok_or_else(|| format!("Unknown enemy: {}", path))
+    })?
+    .into_iter()
+    .map(|(variant, datum)| Ok((variant, optional_position(datum)?)))
+    .collect::<Result<Vec<_>, String>>()?;
+
+    let objective = match objective_kind {
+        1 => Objective::kill_enemies(objective_target),
+        2 => Objective::destroy_buildings(objective_target),
+        3 => Objective::save_animals(objective_target),
+        // Godot counted every enemy killed, minions included, so only the bosses are kept
+        4 => {
+            let bosses = enemies
+                .iter()
+                .filter(|(variant, _)| variant.is_boss())
+                .count();
+            if bosses == 0 {
+                return Err("Boss objective without any bosses".to_owned());
+            }
+            Objective::kill_bosses(objective_target.min(bosses as u16))
+        }
+        kind => return Err(format!("Unknown objective: {}", kind)),
+    };
+
+    // text bubbles
+    let text_bubbles = data
+        .iter()
+        .filter(|datum| datum.get("name").map(String::as_str) == Some("Label"))
+        .map(|datum| {
+            let parent = datum
+                .get("parent")
+                .and_then(|parent| find("name", parent))
+                .ok_or("Label is missing its parent")?;
+            let margin = |key| {
+                parent
+                    .get(key)
+                    .ok_or_else(|| format!("Label parent is missing {}", key))
+                    .and_then(|value| parse_float(value))
+            };
+            Ok(TextBubbleDefinition {
+                position: (margin("margin_left")?, margin("margin_top")?),
+                rows: vec![datum.get("text").cloned().unwrap_or_default()],
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    Ok(LevelDefinition {
+        bgm: bgm::MeadowMeadow,
+        max_score,
+        objective,
+        background: BackgroundDefinition {
+            clear_color,
+            offset,
+            props,
+        },
+        text_bubbles,
+        animals,
+        buildings,
+        enemies,
+        tagged_buildings: vec![],
+        drops: Default::default(),
+        spawners: vec![],
+    })
+}
+
+/// Godot packs tilemap positions into a single int, with the x coordinate in the lower 16 bits
+/// and the y coordinate in the upper bits, both of which can be negative
+fn index_to_xy(index: i64) -> (i32, i32) {
+    const T15: i64 = 1 << 15;
+    const T16: i64 = 1 << 16;
+
+    let m16 = index % T16;
+    let sign = if index < 0 { -1 } else { 1 };
+
+    let x = if m16.abs() < T15 {
+        m16 % T15
+    } else {
+        (m16 % T15) - sign * T15
+    };
+    let y = index.div_euclid(T16);
+    (x as i32, y as i32)
+}
+
+/// Parses the tscn file into a flat list of sections, each section being a map of its properties
+/// (including those in the section header), the section type is stored under the "TYPE" key
+fn parse(source: &str) -> Vec<Datum> {
+    let mut processed = Vec::new();
+    let mut curr: Option<Datum> = None;
+    for line in source.lines() {
+        let line = line
+            .replace("( ", "(")
+            .replace(" )", ")")
+            .replacen(" = ", "=", 1);
+        let line = line.trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        if line.starts_with('[') {
+            processed.extend(curr.take());
+
+            let mut parts = line
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .split(' ');
+            let mut datum = Datum::new();
+            datum.insert(
+                "TYPE".to_owned(),
+                parts.next().unwrap_or_default().to_owned(),
+            );
+            for prop in parts {
+                if let Some((key, value)) = prop.split_once('=') {
+                    datum.insert(key.to_owned(), unquote(value).to_owned());
+                }
+            }
+            curr = Some(datum);
+        } else if let (Some((key, value)), Some(datum)) = (line.split_once('='), &mut curr) {
+            datum.insert(key.to_owned(), unquote(value).to_owned());
+        }
+    }
+    processed.extend(curr);
+
+    processed
+}
+
+/// Returns each instance of an external resource under the given path prefix
+fn instances<'a, T: Copy>(
+    data: &'a [Datum],
+    prefix: &str,
+    to_variant: impl Fn(&str) -> Result<T, String>,
+) -> Result<Vec<(T, &'a Datum)>, String> {
+    let mut exts = HashMap::new();
+    for datum in data {
+        if let (Some(id), Some(path)) = (datum.get("id"), datum.get("path")) {
+            if path.starts_with(prefix) {
+                exts.insert(format!("ExtResource({})", id), to_variant(path)?);
+            }
+        }
+    }
+
+    Ok(data
+        .iter()
+        .filter_map(|datum| {
+            let variant = exts.get(datum.get("instance")?)?;
+            Some((*variant, datum))
+        })
+        .collect())
+}
+
+fn unquote(value: &str) -> &str {
+    let value = value.strip_prefix('"').unwrap_or(value);
+    value.strip_suffix('"').unwrap_or(value)
+}
+
+fn parse_int(value: &str) -> Result<i64, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid integer: \"{}\"", value))
+}
+
+fn parse_float(value: &str) -> Result<f32, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid float: \"{}\"", value))
+}
+
+fn parse_vector2(value: &str) -> Result<(f32, f32), String> {
+    let inner = value
+        .strip_prefix("Vector2(")
+        .and_then(|value| value.strip_suffix(')'))
+        .ok_or_else(|| format!("Invalid Vector2: \"{}\"", value))?;
+    let (x, y) = inner
+        .split_once(',')
+        .ok_or_else(|| format!("Invalid Vector2: \"{}\"", value))?;
+    Ok((parse_float(x.trim())?, parse_float(y.trim())?))
+}
+
+fn optional_position(datum: &Datum) -> Result<(f32, f32), String> {
+    datum
+        .get("position")
+        .map_or(Ok((0., 0.)), |position| parse_vector2(position))
+}
+
+fn parse_color(value: &str) -> Result<(f32, f32, f32, f32), String> {
+    let components = value
+        .trim_start_matches("Color(")
+        .trim_end_matches(')')
+        .split(',')
+        .map(|c| parse_float(c.trim()))
+        .collect::<Result<Vec<_>, _>>()?;
+    match components[..] {
+        [r, g, b, a] => Ok((r, g, b, a)),
+        _ => Err(format!("Invalid Color: \"{}\"", value)),
+    }
+}
+
+/// Returns `Err` for unknown tiles, and `Ok(None)` for known tiles that aren't drawn
+fn tile_to_prop(tile: i64) -> Result<Option<Prop>, ()> {
+    Ok(Some(match tile {
+        0 => Prop::Grass1,
+        1 => Prop::Grass2,
+        2 => Prop::Grass3,
+        3 => Prop::FlowerWhite,
+        7 => Prop::FlowerYellow,
+        23 => Prop::FlowerRed,
+        24 => Prop::FlowerBlack,
+        20 => Prop::Gravel1,
+        21 => Prop::Gravel2,
+        22 => Prop::Gravel3,
+        4 => Prop::Mud,
+        11 => Prop::Hay,
+        25 => Prop::Eggplant,
+        5 | 6 | 12..=19 => return Ok(None),
+        _ => return Err(()),
+    }))
+}
+
+fn building_variant(name: &str) -> Option<BuildingVariant> {
+    Some(match name {
+        "barn" => BuildingVariant::Barn,
+        "car" => BuildingVariant::Car,
+        "concrete_wall_h" => BuildingVariant::ConcreteWallH,
+        "concrete_wall_v" => BuildingVariant::ConcreteWallV,
+        "down_with_horses" => BuildingVariant::DownWithHorses,
+        "feeding_trough" => BuildingVariant::FeedingTrough,
+        "fence" => BuildingVariant::FenceH,
+        "fence_v" => BuildingVariant::FenceV,
+        "garage" => BuildingVariant::Garage,
+        "hay_bale_v" => BuildingVariant::HayBaleV,
+        "hay_bale_h" => BuildingVariant::HayBaleH,
+        "horse_crossing_sign" => BuildingVariant::HorseCrossingSign,
+        "house_1" => BuildingVariant::House1,
+        "house_2" => BuildingVariant::House2,
+        "oil_barrel" => BuildingVariant::OilBarrel,
+        "outhouse" => BuildingVariant::Outhouse,
+        "portapotty" => BuildingVariant::Portapotty,
+        "stable" => BuildingVariant::Stable,
+        "stable_double" => BuildingVariant::StableDouble,
+        "stable_wide" => BuildingVariant::StableWide,
+        "stop_sign" => BuildingVariant::StopSign,
+        "yield_sign" => BuildingVariant::YieldSign,
+        _ => return None,
+    })
+}
+
+fn animal_variant(name: &str) -> Option<AnimalVariant> {
+    Some(match name {
+        "cat" => AnimalVariant::Cat,
+        "dog" => AnimalVariant::Dog,
+        "duck" => AnimalVariant::Duck,
+        "brown_horse" => AnimalVariant::Horse,
+        "kuma" => AnimalVariant::Kuma,
+        "loaf" => AnimalVariant::Loaf,
+        "mouse" => AnimalVariant::Mouse,
+        "poop" => AnimalVariant::Poop,
+        "rabbit" => AnimalVariant::Rabbit,
+        "rubber_ducky" => AnimalVariant::RubberDucky,
+        "snail" => AnimalVariant::Snail,
+        "snake" => AnimalVariant::Snake,
+        "turtle" => AnimalVariant::Turtle,
+        _ => return None,
+    })
+}
+
+fn enemy_variant(name: &str) -> Option<EnemyVariant> {
+    Some(match name {
+        "demon" => EnemyVariant::Demon,
+        "demon_boss" => EnemyVariant::DemonBoss,
+        "farmer" => EnemyVariant::Farmer,
+        "police" => EnemyVariant::Police,
+        "snowman" => EnemyVariant::Snowman,
+        "soldier" => EnemyVariant::Soldier,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_decode_positive_tile_indexes() {
+        assert_eq!(index_to_xy(0), (0, 0));
+        assert_eq!(index_to_xy(2), (2, 0));
+        assert_eq!(index_to_xy(131072), (0, 2));
+        assert_eq!(index_to_xy(196611), (3, 3));
+        assert_eq!(index_to_xy(983071), (31, 15));
+    }
+
+    #[test]
+    fn should_decode_tile_indexes_with_negative_x() {
+        assert_eq!(index_to_xy(65535), (-1, 0));
+        assert_eq!(index_to_xy(65533), (-3, 0));
+        assert_eq!(index_to_xy(262143), (-1, 3));
+        assert_eq!(index_to_xy(196604), (-4, 2));
+        assert_eq!(index_to_xy(1245160), (-24, 18));
+    }
+
+    #[test]
+    fn should_decode_tile_indexes_with_negative_y() {
+        assert_eq!(index_to_xy(-65536), (0, -1));
+        assert_eq!(index_to_xy(-196608), (0, -3));
+        assert_eq!(index_to_xy(-65534), (2, -1));
+        assert_eq!(index_to_xy(-458746), (6, -7));
+        assert_eq!(index_to_xy(-786402), (30, -12));
+    }
+
+    #[test]
+    fn should_decode_tile_indexes_with_negative_x_and_y() {
+        assert_eq!(index_to_xy(-1), (-1, -1));
+        assert_eq!(index_to_xy(-131073), (-1, -3));
+        assert_eq!(index_to_xy(-3), (-3, -1));
+        assert_eq!(index_to_xy(-524278), (10, -8));
+        assert_eq!(index_to_xy(-1507364), (-36, -24));
+    }
+
+    const SCENE: &str = r#"
+[gd_scene load_steps=5 format=2]
+
+[ext_resource path="res://scenarios/base_scenario.tscn" type="PackedScene" id=1]
+[ext_resource path="res://entities/buildings/128/barn_128.tscn" type="PackedScene" id=2]
+[ext_resource path="res://entities/player/brown_horse.tscn" type="PackedScene" id=3]
+[ext_resource path="res://entities/enemies/farmer.tscn" type="PackedScene" id=4]
+
+[node name="Scenario" instance=ExtResource( 1 )]
+background_color = Color( 0.2, 0.3, 0.4, 1 )
+objective = 2
+objective_count = 3
+score = 250
+
+[node name="TileMap" type="TileMap" parent="."]
+tile_data = PoolIntArray( 65535, 0, 0, 131072, 20, 0, 2, 5, 0 )
+
+[node name="barn" parent="Buildings" instance=ExtResource( 2 )]
+position = Vector2( 100, -200.5 )
+
+[node name="horse" parent="Animals" instance=ExtResource( 3 )]
+
+[node name="farmer" parent="Enemies" instance=ExtResource( 4 )]
+position = Vector2( -10, 20 )
+
+[node name="Bubble" type="Control" parent="."]
+margin_left = 12.0
+margin_top = -34.0
+
+[node name="Label" type="Label" parent="Bubble"]
+text = "Hello there"
+"#;
+
+    #[test]
+    fn should_import_a_scene() {
+        let level = import(SCENE).unwrap();
+
+        assert_eq!(level.max_score, 25000);
+        assert_eq!(level.objective.to_string(), "Destroy 3 Buildings");
+        assert_eq!(level.background.clear_color, (0.2, 0.3, 0.4, 1.));
+
+        // the prop at (-1, 0) moves the origin, and tile 5 isn't drawn
+        assert_eq!(level.background.offset, (-32., 0.));
+        let positions: Vec<_> = level.background.props.iter().map(|(pos, _)| *pos).collect();
+        assert_eq!(positions, vec![(0, 0), (1, 2)]);
+
+        assert_eq!(level.buildings.len(), 1);
+        assert_eq!(level.buildings[0].1, (100., -200.5));
+        assert_eq!(level.animals.len(), 1);
+        assert_eq!(level.animals[0].1, (0.00001, 0.));
+        assert_eq!(level.enemies.len(), 1);
+        assert_eq!(level.enemies[0].1, (-10., 20.));
+
+        assert_eq!(level.text_bubbles.len(), 1);
+        assert_eq!(level.text_bubbles[0].position, (12., -34.));
+        assert_eq!(level.text_bubbles[0].rows, vec!["Hello there".to_owned()]);
+    }
+
+    #[test]
+    fn should_reject_unknown_props() {
+        let scene = SCENE.replace("131072, 20, 0", "131072, 99, 0");
+        assert_eq!(import(&scene).err().unwrap(), "Unknown prop: \"99\"");
+    }
+
+    #[test]
+    fn should_only_count_the_bosses_in_the_scene_for_boss_objectives() {
+        let scene = SCENE
+            .replace("objective = 2", "objective = 4")
+            .replace("objective_count = 3", "objective_count = 5");
+        assert_eq!(
+            import(&scene).err().unwrap(),
+            "Boss objective without any bosses"
+        );
+
+        let scene = scene.replace("enemies/farmer.tscn", "enemies/demon_boss.tscn");
+        let level = import(&scene).unwrap();
+        assert_eq!(level.objective.to_string(), "Kill 1 Boss");
+    }
+}
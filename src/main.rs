@@ -3,41 +3,51 @@ use macroquad::prelude::*;
 mod assets;
 mod audio;
 mod camera;
+mod debug;
 mod entities;
 mod input;
 mod levels;
 mod physics;
+mod replay;
+mod rng;
+mod save;
 mod scenes;
+mod simulation;
 mod spritesheet;
 mod text_bubbles;
+mod time_scale;
 
 use assets::Assets;
 use audio::AudioManager;
+use debug::Debug;
 use entities::GenerationalIndex;
 use input::Input;
-use levels::Level;
-use physics::{Physics, PhysicsEvent};
+use replay::Replay;
+use rng::Rng;
+use save::Save;
 use scenes::{Scene, SceneChange};
+use time_scale::TimeScale;
 
+mod ai;
 mod animals;
 mod background;
+mod boss;
 mod buildings;
 mod death_ball;
+mod debris;
+mod drops;
 mod enemies;
+mod fire;
 mod health;
 mod hit_effect;
+mod minimap;
 mod objectives;
-
-mod debug {
-    pub const AUTO_COMPLETE_OBJECTIVES: bool = false;
-    pub const DISABLE_BGM: bool = false;
-    pub const DISABLE_SFX: bool = false;
-    pub const DRAW_COLLIDERS: bool = false;
-    pub const ENABLE_LEVEL_SELECT: bool = false;
-    pub const SHOW_FPS: bool = false;
-}
+mod projectiles;
+mod spawners;
 
 const FPS_SMOOTHING: f32 = 0.9;
+/// Mixed into the seed for the cosmetic stream, so it doesn't mirror the gameplay one
+const COSMETIC_SEED: u64 = 0x2545_f491_4f6c_dd1d;
 
 pub mod groups {
     pub const DEATH_BALL: super::GenerationalIndex = super::GenerationalIndex::single(0);
@@ -50,18 +60,30 @@ pub mod groups {
     pub const ENEMY_ATTACK: u8 = 4;
 
     pub const HIT_EFFECT: u8 = 5;
+
+    pub const PROJECTILE: u8 = 6;
+
+    pub const DEBRIS: u8 = 7;
+
+    pub const FIRE: u8 = 8;
 }
 
 pub struct Resources {
     assets: Assets,
     audio: AudioManager,
     input: Input,
-    physics: Physics,
-    deleted: Vec<GenerationalIndex>,
+    /// Real time since the last frame, scenes that run levels scale it by `time_scale`
     delta: f32,
+    time_scale: TimeScale,
+
+    /// Seeds the gameplay stream of every simulation, so that a seed plus the same inputs
+    /// always plays out the same way
+    seed: u64,
+    /// The cosmetic stream, for anything that doesn't affect gameplay
+    rng: Rng,
 
-    score: u32,
-    beaten: std::collections::HashSet<Level>,
+    save: Save,
+    debug: Debug,
 }
 
 pub fn window_config() -> Conf {
@@ -74,24 +96,67 @@ pub fn window_config() -> Conf {
     }
 }
 
-#[macroquad::main(window_config)]
-async fn main() {
+/// Not supported on linux, where the window is left as is
+pub fn set_fullscreen(fullscreen: bool) {
+    unsafe { get_internal_gl().quad_context.set_fullscreen(fullscreen) }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        [] => macroquad::Window::from_config(window_config(), run(None, None)),
+        ["--seed", seed] => match seed.parse() {
+            Ok(seed) => macroquad::Window::from_config(window_config(), run(Some(seed), None)),
+            Err(_) => usage(),
+        },
+        ["replay", path] => match Replay::load(path) {
+            Ok(replay) => {
+                let seed = Some(replay.seed);
+                macroquad::Window::from_config(window_config(), run(seed, Some(replay)))
+            }
+            Err(err) => {
+                eprintln!("Failed to load replay {}: {}", path, err);
+                std::process::exit(1);
+            }
+        },
+        ["import-tscn", path] => levels::tscn::run(path),
+        _ => usage(),
+    }
+}
+
+fn usage() {
+    eprintln!("Usage: deathball [--seed <number> | replay <file.ron> | import-tscn <file.tscn>]");
+    std::process::exit(1);
+}
+
+async fn run(seed: Option<u64>, replay: Option<Replay>) {
+    let seed = seed.unwrap_or_else(|| (miniquad::date::now() * 1000.) as u64);
+    info!("Seed: {}", seed);
+
+    let save = Save::load();
+    if save.settings.fullscreen {
+        set_fullscreen(true);
+    }
     let mut assets = loading_screen().await;
     let mut res = Resources {
-        audio: AudioManager::new(&mut assets),
+        audio: AudioManager::new(&mut assets, &save.settings),
         assets,
-        input: Input::new(),
-        physics: Physics::new(),
-        deleted: Vec::new(),
+        input: Input::new(&save.settings),
         delta: 0.,
-        score: 0,
-        beaten: Default::default(),
+        time_scale: TimeScale::new(),
+        seed,
+        rng: Rng::new(seed ^ COSMETIC_SEED),
+        save,
+        debug: Debug::new(),
     };
-    let mut physics_events: Vec<PhysicsEvent> = Vec::new();
 
     let mut fps = 0.;
 
-    let mut scene: Box<dyn Scene> = scenes::MainMenu::boxed();
+    // the last scene is the current one, the rest are only drawn underneath it
+    let mut scenes: Vec<Box<dyn Scene>> = vec![match replay {
+        Some(replay) => scenes::Combat::playback(&mut res, replay),
+        None => scenes::MainMenu::boxed(),
+    }];
 
     egui_macroquad::cfg(|ctx| {
         use egui::*;
@@ -132,52 +197,53 @@ async fn main() {
         ctx.set_style(style);
     });
 
-    scene.on_enter(&mut res);
+    scenes[0].on_enter(&mut res);
 
     loop {
         res.delta = get_frame_time();
+        // before any scene update, so that a press that changes scenes isn't seen again by the next
+        res.input.update();
+        res.debug.update(&res.input, &mut res.time_scale);
 
-        match scene.update(&mut res) {
+        match scenes.last_mut().unwrap().update(&mut res) {
             SceneChange::None => {}
             SceneChange::Quit => break,
-            SceneChange::Change(new_scene) => {
-                scene = new_scene;
-                scene.on_enter(&mut res);
+            scene_change => {
+                change_scene(&mut scenes, scene_change, &mut res);
                 next_frame().await;
                 continue;
             }
         }
 
-        // Update subsystems
-        res.input.update();
-        res.physics.update(res.delta, &mut physics_events);
-
-        for event in physics_events.drain(..) {
-            scene.handle_physics_event(&mut res, event);
-        }
-
-        let mut new_scene = SceneChange::None;
-        egui_macroquad::ui(|ctx| new_scene = scene.update_ui(&mut res, ctx));
-        match new_scene {
+        let mut scene_change = SceneChange::None;
+        egui_macroquad::ui(|ctx| {
+            scene_change = scenes.last_mut().unwrap().update_ui(&mut res, ctx);
+            let debug_change = debug::update_ui(&mut res, ctx);
+            if !matches!(debug_change, SceneChange::None) {
+                scene_change = debug_change;
+            }
+        });
+        match scene_change {
             SceneChange::None => {}
             SceneChange::Quit => break,
-            SceneChange::Change(new_scene) => {
+            scene_change => {
                 // needs to be called every if egui has been called this frame
                 egui_macroquad::draw();
 
-                scene = new_scene;
-                scene.on_enter(&mut res);
+                change_scene(&mut scenes, scene_change, &mut res);
                 next_frame().await;
                 continue;
             }
         }
 
         // Draw
-        scene.draw(&res);
+        for scene in &scenes {
+            scene.draw(&res);
+        }
 
         egui_macroquad::draw();
 
-        if crate::debug::SHOW_FPS {
+        if res.debug.show_fps {
             fps = (fps * FPS_SMOOTHING) + ((1. / res.delta) * (1. - FPS_SMOOTHING));
             let text = format!("FPS: {:>6.2}", fps);
             draw_text(&text, screen_width() - 86., 16., 16., WHITE);
@@ -187,6 +253,27 @@ async fn main() {
     }
 }
 
+fn change_scene(scenes: &mut Vec<Box<dyn Scene>>, scene_change: SceneChange, res: &mut Resources) {
+    match scene_change {
+        SceneChange::None | SceneChange::Quit => {}
+        SceneChange::Change(scene) => {
+            scenes.clear();
+            scenes.push(scene);
+            scenes.last_mut().unwrap().on_enter(res);
+        }
+        SceneChange::Push(scene) => {
+            scenes.push(scene);
+            scenes.last_mut().unwrap().on_enter(res);
+        }
+        SceneChange::Pop => {
+            // there always needs to be a current scene
+            if scenes.len() > 1 {
+                scenes.pop();
+            }
+        }
+    }
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 async fn loading_screen() -> Assets {
     egui_macroquad::cfg(|ctx| {
@@ -0,0 +1,132 @@
+//! An overview of the whole level in the corner of the screen, scaled down from the level's
+//! bounds (see `LevelDefinition::bounds`). Clicking on it moves the camera there.
+
+use macroquad::prelude::*;
+
+use crate::{camera::Camera, simulation::Simulation};
+
+/// The longest side, in pixels
+const SIZE: f32 = 240.;
+const MARGIN: f32 = 8.;
+const BORDER_THICKNESS: f32 = 2.;
+
+const BACKGROUND_COLOR: Color = Color::new(0., 0., 0., 0.6);
+const BORDER_COLOR: Color = Color::new(1., 1., 1., 0.5);
+const VIEWPORT_COLOR: Color = WHITE;
+const DESTRUCTIBLE_COLOR: Color = Color::new(0.95, 0.6, 0.2, 1.);
+const INDESTRUCTIBLE_COLOR: Color = Color::new(0.5, 0.5, 0.5, 1.);
+const ENEMY_COLOR: Color = Color::new(0.9, 0.15, 0.15, 1.);
+const BOSS_COLOR: Color = Color::new(0.7, 0.2, 0.9, 1.);
+const ANIMAL_COLOR: Color = Color::new(1., 1., 0.6, 1.);
+const DEATH_BALL_COLOR: Color = Color::new(0.3, 0.8, 1., 1.);
+
+/// Marker sizes, in pixels
+const BUILDING_SIZE: f32 = 4.;
+const ENEMY_RADIUS: f32 = 2.;
+const BOSS_RADIUS: f32 = 5.;
+const ANIMAL_RADIUS: f32 = 1.5;
+const DEATH_BALL_RADIUS: f32 = 4.;
+
+pub struct Minimap {
+    /// The part of the level that's shown, in world coordinates
+    bounds: Rect,
+    /// Where it's drawn, in screen coordinates
+    rect: Rect,
+}
+
+impl Minimap {
+    pub fn new(bounds: Rect) -> Self {
+        let scale = SIZE / bounds.w.max(bounds.h);
+        Minimap {
+            bounds,
+            rect: Rect::new(MARGIN, MARGIN, bounds.w * scale, bounds.h * scale),
+        }
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.rect.contains(point)
+    }
+
+    /// From a point on the minimap to the level, kept within the level's bounds
+    pub fn to_world(&self, point: Vec2) -> Vec2 {
+        let fraction =
+            ((point - self.rect.point()) / self.rect.size()).clamp(Vec2::ZERO, Vec2::ONE);
+        self.bounds.point() + fraction * self.bounds.size()
+    }
+
+    /// From a point in the level to the minimap, kept within the minimap
+    fn to_minimap(&self, point: Vec2) -> Vec2 {
+        let fraction =
+            ((point - self.bounds.point()) / self.bounds.size()).clamp(Vec2::ZERO, Vec2::ONE);
+        self.rect.point() + fraction * self.rect.size()
+    }
+
+    /// In screen coordinates, so the camera needs to be disabled
+    pub fn draw(&self, sim: &Simulation, camera: &Camera) {
+        let Rect { x, y, w, h } = self.rect;
+        draw_rectangle(x, y, w, h, BACKGROUND_COLOR);
+
+        for building in &sim.buildings {
+            let point = self.to_minimap(building.get_position()) - Vec2::splat(BUILDING_SIZE / 2.);
+            let color = if building.is_destructible() {
+                DESTRUCTIBLE_COLOR
+            } else {
+                INDESTRUCTIBLE_COLOR
+            };
+            draw_rectangle(point.x, point.y, BUILDING_SIZE, BUILDING_SIZE, color);
+        }
+        for animal in &sim.animals {
+            if animal.is_alive() && !animal.is_affected_by_death_ball {
+                let point = self.to_minimap(animal.get_position(&sim.world));
+                draw_circle(point.x, point.y, ANIMAL_RADIUS, ANIMAL_COLOR);
+            }
+        }
+        for enemy in &sim.enemies {
+            let point = self.to_minimap(enemy.get_position(&sim.world));
+            if enemy.boss_status().is_some() {
+                draw_circle(point.x, point.y, BOSS_RADIUS, BOSS_COLOR);
+            } else if enemy.is_alive() {
+                draw_circle(point.x, point.y, ENEMY_RADIUS, ENEMY_COLOR);
+            }
+        }
+        let point = self.to_minimap(sim.death_ball.get_position(&sim.world));
+        draw_circle(point.x, point.y, DEATH_BALL_RADIUS, DEATH_BALL_COLOR);
+
+        let min = self.to_minimap(camera.screen_to_world(Vec2::ZERO));
+        let max = self.to_minimap(camera.screen_to_world(vec2(screen_width(), screen_height())));
+        let size = max - min;
+        draw_rectangle_lines(
+            min.x,
+            min.y,
+            size.x,
+            size.y,
+            BORDER_THICKNESS,
+            VIEWPORT_COLOR,
+        );
+
+        draw_rectangle_lines(x, y, w, h, BORDER_THICKNESS, BORDER_COLOR);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_map_between_the_minimap_and_the_level() {
+        let minimap = Minimap::new(Rect::new(-1000., -500., 4000., 2000.));
+        assert_eq!(minimap.rect.size(), vec2(SIZE, SIZE / 2.));
+
+        let point = vec2(1000., 0.);
+        assert_eq!(minimap.to_world(minimap.to_minimap(point)), point);
+        assert_eq!(
+            minimap.to_minimap(vec2(-1000., -500.)),
+            vec2(MARGIN, MARGIN)
+        );
+        assert_eq!(
+            minimap.to_minimap(vec2(99999., 0.)).x,
+            MARGIN + SIZE,
+            "kept within the minimap"
+        );
+    }
+}
@@ -1,23 +1,95 @@
+//! What a level asks of the player, see `LevelDefinition::objective`.
+//!
+//! Objectives can be nested with `AllOf` and `AnyOf`, and carry bonus objectives that add to the
+//! score once complete without being needed to win. Once complete or failed, an objective stays
+//! that way.
+
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::animals::Variant as AnimalVariant;
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Objective {
     kind: Kind,
-    current: u8,
-    target: u8,
+    #[serde(skip)]
+    current: u16,
+    /// Ignored by `AllOf` and `AnyOf`, which count their objectives instead
+    #[serde(default = "default_target")]
+    target: u16,
+    /// In seconds since the start of the level, fails the objective if it isn't complete by then
+    #[serde(default)]
+    time_limit: Option<f32>,
+    #[serde(default)]
+    bonus: Vec<Bonus>,
+    #[serde(skip)]
+    state: State,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 enum Kind {
     None,
     SaveAnimals,
     DestroyBuildings,
     KillEnemies,
     KillBosses,
+    SurviveWaves,
+    /// In seconds
+    SurviveTime,
+    /// Buildings given this tag by `LevelDefinition::tagged_buildings`
+    DestroyTagged(String),
+    /// Have the death ball (and at least one animal) within the radius of the position
+    ReachZone {
+        position: (f32, f32),
+        radius: f32,
+    },
+    /// Have this many of the variant in the death ball at once
+    CollectAnimals(AnimalVariant),
+    AllOf(Vec<Objective>),
+    AnyOf(Vec<Objective>),
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Bonus {
+    pub objective: Objective,
+    pub score: u32,
+    #[serde(skip)]
+    is_awarded: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Default)]
+enum State {
+    #[default]
+    InProgress,
+    Complete,
+    Failed,
+}
+
+fn default_target() -> u16 {
+    1
+}
+
+/// Returns how many of the objectives are complete, and how many have failed
+fn update_each(objectives: &mut [Objective], time: f32) -> (usize, usize) {
+    let mut complete = 0;
+    let mut failed = 0;
+    for objective in objectives {
+        objective.update(time);
+        complete += objective.is_complete() as usize;
+        failed += objective.is_failed() as usize;
+    }
+    (complete, failed)
 }
 
 impl Objective {
-    fn new(kind: Kind, target: u8) -> Self {
+    fn new(kind: Kind, target: u16) -> Self {
         Self {
             current: 0,
             target,
             kind,
+            time_limit: None,
+            bonus: Vec::new(),
+            state: State::InProgress,
         }
     }
 
@@ -25,57 +97,215 @@ impl Objective {
         Self::new(Kind::None, 1)
     }
 
-    pub fn save_animals(target: u8) -> Self {
+    pub fn save_animals(target: u16) -> Self {
         Self::new(Kind::SaveAnimals, target)
     }
 
-    pub fn destroy_buildings(target: u8) -> Self {
+    pub fn destroy_buildings(target: u16) -> Self {
         Self::new(Kind::DestroyBuildings, target)
     }
 
-    pub fn kill_enemies(target: u8) -> Self {
+    pub fn kill_enemies(target: u16) -> Self {
         Self::new(Kind::KillEnemies, target)
     }
 
-    pub fn kill_bosses(target: u8) -> Self {
+    pub fn kill_bosses(target: u16) -> Self {
         Self::new(Kind::KillBosses, target)
     }
 
     pub fn is_complete(&self) -> bool {
-        crate::debug::AUTO_COMPLETE_OBJECTIVES || self.current >= self.target
+        self.state == State::Complete
+    }
+
+    /// Skips straight to the end, for testing
+    pub fn complete(&mut self) {
+        self.state = State::Complete;
+    }
+
+    pub fn is_failed(&self) -> bool {
+        self.state == State::Failed
     }
 
-    pub fn on_update_death_ball_count(&mut self, current: u8) {
-        if let Kind::SaveAnimals { .. } = self.kind {
-            self.current = current;
+    pub fn current(&self) -> u16 {
+        self.current
+    }
+
+    pub fn target(&self) -> u16 {
+        match &self.kind {
+            Kind::AllOf(objectives) => objectives.len() as u16,
+            Kind::AnyOf(_) => 1,
+            _ => self.target,
         }
     }
 
-    pub fn on_kill_enemy(&mut self) {
-        if let Kind::KillEnemies { .. } | Kind::KillBosses { .. } = self.kind {
-            self.current += 1;
+    /// The objectives of an `AllOf` or `AnyOf`, empty otherwise
+    pub fn objectives(&self) -> &[Objective] {
+        match &self.kind {
+            Kind::AllOf(objectives) | Kind::AnyOf(objectives) => objectives,
+            _ => &[],
         }
     }
 
-    pub fn on_destroy_building(&mut self) {
-        if let Kind::DestroyBuildings { .. } = self.kind {
-            self.current += 1;
+    pub fn bonus(&self) -> &[Bonus] {
+        &self.bonus
+    }
+
+    pub fn time_left(&self, time: f32) -> Option<f32> {
+        self.time_limit.map(|limit| (limit - time).max(0.))
+    }
+
+    /// Every zone the death ball needs to be escorted to, as a position and radius
+    pub fn zones(&self) -> Vec<(Vec2, f32)> {
+        let mut zones = Vec::new();
+        self.visit(&mut |objective| {
+            if let Kind::ReachZone { position, radius } = objective.kind {
+                zones.push((position.into(), radius));
+            }
+        });
+        zones
+    }
+
+    /// Completes or fails objectives now that their progress has been updated
+    pub fn update(&mut self, time: f32) {
+        for bonus in &mut self.bonus {
+            bonus.objective.update(time);
+        }
+        if self.state != State::InProgress {
+            return;
+        }
+
+        let (is_complete, is_failed) = match &mut self.kind {
+            Kind::AllOf(objectives) => {
+                let (complete, failed) = update_each(objectives, time);
+                self.current = complete as u16;
+                (complete == objectives.len(), failed > 0)
+            }
+            Kind::AnyOf(objectives) => {
+                let (complete, failed) = update_each(objectives, time);
+                self.current = complete as u16;
+                (complete > 0, failed == objectives.len())
+            }
+            _ => (self.current >= self.target, false),
+        };
+        let is_out_of_time = self.time_limit.is_some_and(|limit| time > limit);
+
+        if is_complete {
+            self.state = State::Complete;
+        } else if is_failed || is_out_of_time {
+            self.state = State::Failed;
         }
     }
 
-    pub fn current(&self) -> u8 {
-        self.current
+    /// The score from bonus objectives completed since this was last called
+    pub fn take_bonus_score(&mut self) -> u32 {
+        let mut score = 0;
+        for bonus in &mut self.bonus {
+            if bonus.objective.is_complete() && !bonus.is_awarded {
+                bonus.is_awarded = true;
+                score += bonus.score;
+            }
+        }
+        score
+    }
+
+    pub fn on_update_death_ball(&mut self, animals: &[AnimalVariant]) {
+        self.visit_mut(&mut |objective| match objective.kind {
+            Kind::SaveAnimals => objective.current = animals.len() as u16,
+            Kind::CollectAnimals(variant) => {
+                let count = animals.iter().filter(|&&other| other == variant).count();
+                objective.current = count as u16;
+            }
+            _ => {}
+        });
+    }
+
+    /// An empty death ball doesn't count as having reached a zone
+    pub fn on_move_death_ball(&mut self, death_ball: Vec2, size: u8) {
+        self.visit_mut(&mut |objective| {
+            if let Kind::ReachZone { position, radius } = objective.kind {
+                let is_inside = death_ball.distance(position.into()) <= radius;
+                objective.current = (is_inside && size > 0) as u16;
+            }
+        });
+    }
+
+    pub fn on_survive_waves(&mut self, waves: u16) {
+        self.visit_mut(&mut |objective| {
+            if let Kind::SurviveWaves = objective.kind {
+                objective.current = waves;
+            }
+        });
+    }
+
+    pub fn on_survive_time(&mut self, time: f32) {
+        self.visit_mut(&mut |objective| {
+            if let Kind::SurviveTime = objective.kind {
+                objective.current = time as u16;
+            }
+        });
+    }
+
+    pub fn on_kill_enemy(&mut self, is_boss: bool) {
+        self.visit_mut(&mut |objective| match objective.kind {
+            Kind::KillEnemies => objective.current += 1,
+            Kind::KillBosses if is_boss => objective.current += 1,
+            _ => {}
+        });
+    }
+
+    pub fn on_destroy_building(&mut self, tag: Option<&str>) {
+        self.visit_mut(&mut |objective| match &objective.kind {
+            Kind::DestroyBuildings => objective.current += 1,
+            Kind::DestroyTagged(other) if Some(other.as_str()) == tag => objective.current += 1,
+            _ => {}
+        });
+    }
+
+    /// Calls `f` on this objective and every one nested in it, bonus objectives included
+    fn visit(&self, f: &mut impl FnMut(&Objective)) {
+        f(self);
+        for objective in self.objectives() {
+            objective.visit(f);
+        }
+        for bonus in &self.bonus {
+            bonus.objective.visit(f);
+        }
+    }
+
+    /// Like `visit`, but skips objectives that are already complete or failed
+    fn visit_mut(&mut self, f: &mut impl FnMut(&mut Objective)) {
+        if self.state == State::InProgress {
+            f(self);
+        }
+        if let Kind::AllOf(objectives) | Kind::AnyOf(objectives) = &mut self.kind {
+            for objective in objectives {
+                objective.visit_mut(f);
+            }
+        }
+        for bonus in &mut self.bonus {
+            bonus.objective.visit_mut(f);
+        }
     }
 }
 
-impl std::fmt::Display for Objective {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let (prefix, suffix_singular, suffix_plural) = match self.kind {
+impl Objective {
+    /// What to do, without the time limit
+    fn fmt_kind(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (prefix, suffix_singular, suffix_plural) = match &self.kind {
             Kind::None => return f.write_str("None"),
             Kind::SaveAnimals => ("Save", "Animal", "Animals"),
             Kind::DestroyBuildings => ("Destroy", "Building", "Buildings"),
             Kind::KillEnemies => ("Kill", "Enemy", "Enemies"),
             Kind::KillBosses => ("Kill", "Boss", "Bosses"),
+            Kind::SurviveWaves => ("Survive", "Wave", "Waves"),
+            Kind::SurviveTime => ("Survive", "Second", "Seconds"),
+            Kind::DestroyTagged(tag) => return write!(f, "Destroy {} {}", self.target, tag),
+            Kind::ReachZone { .. } => return f.write_str("Escort the Deathball to the Zone"),
+            Kind::CollectAnimals(variant) => {
+                return write!(f, "Collect {} {:?}", self.target, variant)
+            }
+            Kind::AllOf(_) => return f.write_str("Complete All"),
+            Kind::AnyOf(_) => return f.write_str("Complete Any"),
         };
         let suffix = if self.target == 1 {
             suffix_singular
@@ -85,3 +315,114 @@ impl std::fmt::Display for Objective {
         write!(f, "{} {} {}", prefix, self.target, suffix)
     }
 }
+
+impl std::fmt::Display for Objective {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.fmt_kind(f)?;
+        if let Some(limit) = self.time_limit {
+            write!(f, " in {}s", limit)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Objective {
+        ron::from_str(source).unwrap()
+    }
+
+    #[test]
+    fn should_complete_all_of_once_every_objective_is() {
+        let mut objective = parse(
+            "(kind: AllOf([(kind: KillEnemies, target: 2), (kind: DestroyTagged(\"Silo\"))]))",
+        );
+        objective.on_kill_enemy(false);
+        objective.on_kill_enemy(false);
+        objective.on_destroy_building(None);
+        objective.update(1.);
+        assert!(!objective.is_complete());
+        assert_eq!(objective.current(), 1);
+
+        objective.on_destroy_building(Some("Silo"));
+        objective.update(2.);
+        assert!(objective.is_complete());
+        assert_eq!(objective.current(), 2);
+    }
+
+    #[test]
+    fn should_complete_any_of_once_one_objective_is() {
+        let mut objective = parse(
+            "(kind: AnyOf([(kind: CollectAnimals(Kuma), target: 2), \
+              (kind: ReachZone(position: (100., 0.), radius: 50.))]))",
+        );
+        objective.on_update_death_ball(&[AnimalVariant::Kuma, AnimalVariant::Horse]);
+        objective.update(1.);
+        assert!(!objective.is_complete());
+
+        objective.on_move_death_ball(vec2(120., 0.), 2);
+        objective.update(2.);
+        assert!(objective.is_complete());
+    }
+
+    #[test]
+    fn should_need_animals_in_the_death_ball_to_reach_a_zone() {
+        let mut objective = parse("(kind: ReachZone(position: (100., 0.), radius: 50.))");
+        objective.on_move_death_ball(vec2(100., 0.), 0);
+        objective.update(1.);
+        assert!(!objective.is_complete());
+
+        objective.on_move_death_ball(vec2(100., 0.), 1);
+        objective.update(2.);
+        assert!(objective.is_complete());
+    }
+
+    #[test]
+    fn should_show_the_time_limit_for_every_kind() {
+        let objective = parse("(kind: KillEnemies, target: 3, time_limit: Some(30.))");
+        assert_eq!(objective.to_string(), "Kill 3 Enemies in 30s");
+        let objective =
+            parse("(kind: ReachZone(position: (0., 0.), radius: 50.), time_limit: Some(60.))");
+        assert_eq!(
+            objective.to_string(),
+            "Escort the Deathball to the Zone in 60s"
+        );
+        let objective = parse("(kind: CollectAnimals(Kuma), target: 2, time_limit: Some(5.))");
+        assert_eq!(objective.to_string(), "Collect 2 Kuma in 5s");
+    }
+
+    #[test]
+    fn should_fail_once_out_of_time() {
+        let mut objective = parse(
+            "(kind: AllOf([(kind: KillEnemies, time_limit: Some(10.)), (kind: KillBosses)]))",
+        );
+        objective.on_kill_enemy(false);
+        objective.update(5.);
+        objective.update(20.);
+        assert!(!objective.is_failed(), "completed before the time limit");
+
+        let mut objective = parse("(kind: KillBosses, time_limit: Some(10.))");
+        objective.update(5.);
+        assert!(!objective.is_failed());
+        objective.update(20.);
+        assert!(objective.is_failed());
+        objective.on_kill_enemy(true);
+        objective.update(21.);
+        assert!(!objective.is_complete());
+    }
+
+    #[test]
+    fn should_award_bonus_score_once() {
+        let mut objective = parse(
+            "(kind: KillBosses, bonus: [(objective: (kind: KillEnemies, target: 2), score: 500)])",
+        );
+        objective.on_kill_enemy(false);
+        objective.on_kill_enemy(false);
+        objective.update(1.);
+        assert_eq!(objective.take_bonus_score(), 500);
+        assert_eq!(objective.take_bonus_score(), 0);
+        assert!(!objective.is_complete());
+    }
+}
@@ -9,12 +9,9 @@ use rapier2d::prelude::*;
 
 use crate::entities::GenerationalIndex;
 
-const DT: f32 = 1. / 60.; // ie. 1 / intended FPS
-const MAX_STEPS: u8 = 6;
+pub const DT: f32 = 1. / 60.; // ie. 1 / intended FPS
 
 pub struct Physics {
-    accumulator: f32,
-
     physics_pipeline: PhysicsPipeline,
     integration_parameters: IntegrationParameters,
     island_manager: IslandManager,
@@ -24,6 +21,7 @@ pub struct Physics {
     collider_set: ColliderSet,
     joint_set: JointSet,
     ccd_solver: CCDSolver,
+    query_pipeline: QueryPipeline,
 
     events: Vec<(PhysicsEventKind, ColliderHandle, ColliderHandle)>,
 }
@@ -31,7 +29,6 @@ pub struct Physics {
 impl Physics {
     pub fn new() -> Self {
         Physics {
-            accumulator: 0.,
             physics_pipeline: PhysicsPipeline::new(),
             integration_parameters: IntegrationParameters::default(),
             island_manager: IslandManager::new(),
@@ -41,33 +38,31 @@ impl Physics {
             collider_set: ColliderSet::new(),
             joint_set: JointSet::new(),
             ccd_solver: CCDSolver::new(),
+            query_pipeline: QueryPipeline::new(),
             events: Vec::new(),
         }
     }
 
-    pub fn update(&mut self, delta: f32, events: &mut Vec<PhysicsEvent>) {
-        // source: https://gafferongames.com/post/fix_your_timestep
-        self.accumulator += delta;
-        let mut steps_taken = 0;
-        while self.accumulator >= DT && steps_taken < MAX_STEPS {
-            steps_taken += 1;
-
-            self.physics_pipeline.step(
-                &vector![0., 0.],
-                &self.integration_parameters,
-                &mut self.island_manager,
-                &mut self.broad_phase,
-                &mut self.narrow_phase,
-                &mut self.rigid_body_set,
-                &mut self.collider_set,
-                &mut self.joint_set,
-                &mut self.ccd_solver,
-                &(),
-                &RawEventCollector(Mutex::new(&mut self.events)),
-            );
-
-            self.accumulator -= DT;
-        }
+    /// Advances the physics world by a single step of `DT`
+    pub fn step(&mut self, events: &mut Vec<PhysicsEvent>) {
+        self.physics_pipeline.step(
+            &vector![0., 0.],
+            &self.integration_parameters,
+            &mut self.island_manager,
+            &mut self.broad_phase,
+            &mut self.narrow_phase,
+            &mut self.rigid_body_set,
+            &mut self.collider_set,
+            &mut self.joint_set,
+            &mut self.ccd_solver,
+            &(),
+            &RawEventCollector(Mutex::new(&mut self.events)),
+        );
+        self.query_pipeline.update(
+            &self.island_manager,
+            &self.rigid_body_set,
+            &self.collider_set,
+        );
 
         for (kind, handle1, handle2) in self.events.drain(..) {
             events.push(PhysicsEvent::new(
@@ -156,11 +151,6 @@ impl Physics {
     //     KinematicHandle(collider_handle, rigid_body_handle)
     // }
 
-    pub fn reset(&mut self) {
-        let old = std::mem::replace(self, Self::new());
-        drop(old);
-    }
-
     pub fn remove(&mut self, handle: impl Into<Handle>) {
         match handle.into() {
             Handle::Static(StaticHandle(handle)) | Handle::Sensor(SensorHandle(handle)) => {
@@ -193,6 +183,11 @@ impl Physics {
         body.set_translation(position.into());
     }
 
+    pub fn set_ball_radius(&mut self, handle: impl Into<ColliderHandle>, radius: f32) {
+        let body = &mut self.collider_set[handle.into()];
+        body.set_shape(SharedShape::ball(radius));
+    }
+
     pub fn get_position(&self, handle: impl Into<ColliderHandle>) -> Vec2 {
         let body = &self.collider_set[handle.into()];
         (*body.translation()).into()
@@ -221,6 +216,25 @@ impl Physics {
         self.rigid_body_set[handle.into()].apply_impulse(impulse.into(), true);
     }
 
+    /// Whether a ball moving by `motion` would run into a static collider, as of the last step
+    pub fn is_blocked(&self, position: Vec2, radius: f32, motion: Vec2) -> bool {
+        let is_static = |handle: ColliderHandle| {
+            let collider = &self.collider_set[handle];
+            collider.parent().is_none() && !collider.is_sensor()
+        };
+        self.query_pipeline
+            .cast_shape(
+                &self.collider_set,
+                &Isometry::translation(position.x, position.y),
+                &vector![motion.x, motion.y],
+                &Ball::new(radius),
+                1.,
+                InteractionGroups::all(),
+                Some(&is_static),
+            )
+            .is_some()
+    }
+
     pub fn draw_colliders(&self) {
         use nalgebra::ComplexField;
 
@@ -351,6 +365,11 @@ impl MyColliderBuilder {
         self
     }
 
+    pub fn sensor(mut self) -> Self {
+        self.inner.is_sensor = true;
+        self
+    }
+
     pub fn contact_events(mut self) -> Self {
         self.inner.active_events |= ActiveEvents::CONTACT_EVENTS;
         self
@@ -0,0 +1,76 @@
+use macroquad::prelude::*;
+
+use crate::{entities::GenerationalIndex, physics, simulation::World};
+
+const RADIUS: f32 = 6.;
+const LIFETIME: f32 = 3.;
+const COLOR: Color = Color::new(0.192, 0.192, 0.192, 1.);
+
+enum Status {
+    Flying {
+        timer: f32,
+    },
+    /// Has hit something, and will be removed on the next update
+    Spent,
+}
+
+pub struct Projectile {
+    idx: GenerationalIndex,
+    handle: physics::DynamicHandle,
+    status: Status,
+    pub direction: Vec2,
+    pub impulse: f32,
+    pub damage: u8,
+}
+
+impl Projectile {
+    pub fn new(
+        idx: GenerationalIndex,
+        world: &mut World,
+        position: Vec2,
+        velocity: Vec2,
+        impulse: f32,
+        damage: u8,
+    ) -> Self {
+        // a dynamic body so that it hits (static) buildings too, but a sensor so that it doesn't
+        // push anything around itself
+        let collider = physics::ball(RADIUS).sensor().intersection_events();
+        let handle = world.physics.add_dynamic(idx, collider, position);
+        world.physics.set_linear_velocity(handle, velocity);
+
+        Projectile {
+            idx,
+            handle,
+            status: Status::Flying { timer: 0. },
+            direction: velocity.normalize_or_zero(),
+            impulse,
+            damage,
+        }
+    }
+
+    /// Returns whether or not this was the first hit, as it can only hit one thing
+    pub fn hit(&mut self) -> bool {
+        let is_first = matches!(self.status, Status::Flying { .. });
+        self.status = Status::Spent;
+        is_first
+    }
+
+    pub fn update(&mut self, world: &mut World) {
+        let is_done = match self.status {
+            Status::Flying { ref mut timer } => {
+                *timer += world.delta;
+                *timer > LIFETIME
+            }
+            Status::Spent => true,
+        };
+        if is_done {
+            world.physics.remove(self.handle);
+            world.deleted.push(self.idx);
+        }
+    }
+
+    pub fn draw(&self, world: &World) {
+        let (x, y) = world.physics.get_position(self.handle).into();
+        draw_circle(x, y, RADIUS, COLOR);
+    }
+}
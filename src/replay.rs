@@ -0,0 +1,201 @@
+//! Recordings of a combat level that can be played back exactly, see `scenes::Combat`.
+//!
+//! Along with the level and seed, every frame stores the input and how many fixed simulation
+//! steps were taken, so playback doesn't depend on the frame rate it was recorded at.
+
+use std::path::PathBuf;
+
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    camera::Camera,
+    input::{Input, MouseDrag},
+    levels::Level,
+    simulation::{Cheat, Commands},
+};
+
+const REPLAYS_DIR: &str = "./replays";
+
+#[derive(Serialize, Deserialize)]
+pub struct Replay {
+    pub level: Level,
+    pub seed: u64,
+    pub frames: Vec<Frame>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Frame {
+    pub steps: u8,
+    /// In world coordinates, so that playback doesn't depend on the window size
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub move_deathball: Option<(f32, f32)>,
+    #[serde(alias = "pan_camera_keyboard", skip_serializing_if = "is_zero")]
+    pub pan_camera: (f32, f32),
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pan_camera_mouse_drag: Option<((f32, f32), (f32, f32))>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub zoom_camera: Option<f32>,
+    #[serde(skip_serializing_if = "is_false")]
+    pub reset_camera: bool,
+    #[serde(skip_serializing_if = "is_false")]
+    pub go_to_next_scene: bool,
+    /// Carried out before the steps
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub cheats: Vec<Cheat>,
+}
+
+impl Replay {
+    pub fn new(level: Level, seed: u64) -> Self {
+        Replay {
+            level,
+            seed,
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let source = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let replay: Replay = ron::from_str(&source).map_err(|err| err.to_string())?;
+        if replay.level == Level::Custom {
+            return Err("custom levels can't be played back".to_owned());
+        }
+        Ok(replay)
+    }
+
+    /// Returns the path the replay was saved to, which is timestamped so that saving again after
+    /// retrying the level doesn't overwrite it
+    pub fn save(&self) -> std::io::Result<PathBuf> {
+        let source = ron::to_string(self).unwrap();
+        let timestamp = miniquad::date::now() as u64;
+        let name = format!("{:?}-{}-{}.ron", self.level, self.seed, timestamp);
+        let path = PathBuf::from(REPLAYS_DIR).join(name);
+        std::fs::create_dir_all(REPLAYS_DIR)?;
+        std::fs::write(&path, source)?;
+        Ok(path)
+    }
+}
+
+impl Frame {
+    /// Takes the current live input, `steps` is left to be filled in once they've been taken
+    pub fn from_input(input: &Input, camera: &Camera) -> Self {
+        Frame {
+            steps: 0,
+            move_deathball: input
+                .move_deathball()
+                .map(|position| camera.screen_to_world(position).into()),
+            pan_camera: input.pan_camera().into(),
+            pan_camera_mouse_drag: input
+                .pan_camera_mouse_drag()
+                .map(|drag| (drag.previous.into(), drag.current.into())),
+            zoom_camera: input.zoom_camera(),
+            reset_camera: input.reset_camera(),
+            go_to_next_scene: input.go_to_next_scene(),
+            cheats: Vec::new(),
+        }
+    }
+
+    pub fn commands(&self) -> Commands {
+        Commands {
+            move_deathball: self.move_deathball.map(Vec2::from),
+        }
+    }
+
+    pub fn pan_camera_mouse_drag(&self) -> Option<MouseDrag> {
+        self.pan_camera_mouse_drag
+            .map(|(previous, current)| MouseDrag {
+                previous: previous.into(),
+                current: current.into(),
+            })
+    }
+}
+
+fn is_zero(value: &(f32, f32)) -> bool {
+    *value == (0., 0.)
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        enemies,
+        levels::LevelDefinition,
+        rng::Rng,
+        simulation::{Simulation, Spawnable},
+    };
+
+    fn load_scenario_1(seed: u64) -> Simulation {
+        let source = include_str!("../assets/levels/scenario_1.ron");
+        let definition = LevelDefinition::parse(source).unwrap();
+        Simulation::new(Level::Scenario1, &definition, Rng::new(seed))
+    }
+
+    #[test]
+    fn should_only_write_non_default_fields() {
+        let frame = Frame {
+            steps: 1,
+            go_to_next_scene: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            ron::to_string(&frame).unwrap(),
+            "(steps:1,go_to_next_scene:true)"
+        );
+    }
+
+    #[test]
+    fn should_reproduce_the_recorded_simulation() {
+        let mut replay = Replay::new(Level::Scenario1, 42);
+
+        // record an uneven number of steps per frame, as happens with a variable frame rate
+        let mut recorded = load_scenario_1(replay.seed);
+        for i in 0..600 {
+            let mut frame = Frame {
+                steps: (i % 3) as u8,
+                move_deathball: Some((i as f32, -(i as f32) * 2.)),
+                ..Default::default()
+            };
+            if i == 100 {
+                let soldier = Spawnable::Enemy(enemies::Variant::Soldier);
+                frame.cheats = vec![
+                    Cheat::SetDeathBallSize(20),
+                    Cheat::Spawn(soldier, (0., 300.)),
+                ];
+            }
+            for cheat in &frame.cheats {
+                recorded.cheat(cheat);
+            }
+            for _ in 0..frame.steps {
+                recorded.step(&frame.commands());
+            }
+            replay.frames.push(frame);
+        }
+
+        let source = ron::to_string(&replay).unwrap();
+        let replay: Replay = ron::from_str(&source).unwrap();
+
+        let mut played = load_scenario_1(replay.seed);
+        for frame in &replay.frames {
+            for cheat in &frame.cheats {
+                played.cheat(cheat);
+            }
+            for _ in 0..frame.steps {
+                played.step(&frame.commands());
+            }
+        }
+
+        let positions = |sim: &Simulation| -> Vec<Vec2> {
+            sim.animals
+                .into_iter()
+                .map(|animal| animal.get_position(&sim.world))
+                .collect()
+        };
+        assert_eq!(positions(&played), positions(&recorded));
+        assert_eq!(played.score, recorded.score);
+    }
+}
@@ -0,0 +1,102 @@
+//! A seedable random number generator.
+//!
+//! This is the same PCG generator that backs macroquad's global `rand`, but as a value so that
+//! gameplay and cosmetic randomness can be separate streams that are seeded independently.
+
+const INCREMENT: u64 = 1442695040888963407;
+const MULTIPLIER: u64 = 6364136223846793005;
+
+#[derive(Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // seeded the same way as quad_rand::srand
+        let mut rng = Rng { state: 0 };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(MULTIPLIER).wrapping_add(INCREMENT);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        let rot = (old >> 59) as u32;
+        xorshifted.rotate_right(rot)
+    }
+
+    /// A separate stream derived from the current state, without advancing this one
+    pub fn fork(&self, salt: u64) -> Rng {
+        Rng::new(self.state ^ salt)
+    }
+
+    /// Returns a random value in the range `low..high`, or `low` if the range is empty
+    pub fn gen_range<T: RandomRange>(&mut self, low: T, high: T) -> T {
+        T::gen_range(self, low, high)
+    }
+}
+
+pub trait RandomRange {
+    fn gen_range(rng: &mut Rng, low: Self, high: Self) -> Self;
+}
+
+impl RandomRange for f32 {
+    fn gen_range(rng: &mut Rng, low: f32, high: f32) -> f32 {
+        let r = rng.next_u32() as f32 / u32::MAX as f32;
+        low + (high - low) * r
+    }
+}
+
+impl RandomRange for usize {
+    fn gen_range(rng: &mut Rng, low: usize, high: usize) -> usize {
+        if high <= low {
+            return low;
+        }
+        low + rng.next_u32() as usize % (high - low)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_repeat_the_same_sequence_for_the_same_seed() {
+        let mut a = Rng::new(1234);
+        let mut b = Rng::new(1234);
+        for _ in 0..100 {
+            assert_eq!(a.next_u32(), b.next_u32());
+        }
+    }
+
+    #[test]
+    fn should_produce_different_sequences_for_different_seeds() {
+        let mut a = Rng::new(1);
+        let mut b = Rng::new(2);
+        let a: Vec<_> = (0..10).map(|_| a.next_u32()).collect();
+        let b: Vec<_> = (0..10).map(|_| b.next_u32()).collect();
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn should_stay_within_the_given_range() {
+        let mut rng = Rng::new(0);
+        for _ in 0..1000 {
+            let f = rng.gen_range(-2.5, 2.5);
+            assert!((-2.5..=2.5).contains(&f));
+            let i = rng.gen_range(3, 7);
+            assert!((3..7).contains(&i));
+        }
+    }
+
+    #[test]
+    fn should_return_the_low_end_of_an_empty_range() {
+        let mut rng = Rng::new(0);
+        assert_eq!(rng.gen_range(4, 4), 4);
+        assert_eq!(rng.gen_range(0, 0), 0);
+    }
+}
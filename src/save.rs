@@ -0,0 +1,316 @@
+//! Progress and settings that persist between sessions.
+//!
+//! Saved as RON to the user's data directory on native, and to `localStorage` on the web.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    animals::Variant as AnimalVariant, camera::Follow, input::bindings::Bindings, levels::Level,
+};
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Save {
+    pub beaten: HashSet<Level>,
+    /// The sum of the best score on each level
+    pub score: u32,
+    pub records: HashMap<Level, Record>,
+    /// For levels that can only be lost, see `LevelDefinition::spawners`
+    pub most_waves_survived: HashMap<Level, u16>,
+    pub settings: Settings,
+}
+
+/// How well a level went, when kept in `Save::records` each field is the best across all wins
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Record {
+    pub score: u32,
+    pub time: f32,
+    pub peak_death_ball_size: u8,
+    pub stars: u8,
+    /// Every animal dropped by buildings, across all wins
+    #[serde(default)]
+    pub found: BTreeSet<AnimalVariant>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub bgm_volume: f64,
+    pub sfx_volume: f64,
+    /// World units per frame when panning the camera with the keyboard
+    pub pan_speed: f32,
+    /// Multiplies how far each step of the mouse wheel zooms
+    pub zoom_sensitivity: f32,
+    pub fullscreen: bool,
+    pub camera_follow: Follow,
+    pub screen_shake: bool,
+    pub bindings: Bindings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            bgm_volume: 0.5,
+            sfx_volume: 0.5,
+            pan_speed: 15.,
+            zoom_sensitivity: 1.,
+            fullscreen: false,
+            camera_follow: Follow::Off,
+            screen_shake: true,
+            bindings: Bindings::default(),
+        }
+    }
+}
+
+impl Save {
+    /// Falls back to an empty save if there isn't one yet (or it can't be read)
+    pub fn load() -> Self {
+        match storage::read() {
+            Some(source) => Save::parse(&source).unwrap_or_else(|err| {
+                error!("Failed to parse save: {}", err);
+                Save::default()
+            }),
+            None => Save::default(),
+        }
+    }
+
+    pub fn store(&self) {
+        storage::write(&ron::to_string(self).unwrap());
+    }
+
+    fn parse(source: &str) -> ron::Result<Self> {
+        ron::from_str(source)
+    }
+
+    pub fn has_progress(&self) -> bool {
+        !self.beaten.is_empty()
+    }
+
+    /// Clears all progress, but keeps the settings
+    pub fn reset_progress(&mut self) {
+        *self = Save {
+            settings: self.settings.clone(),
+            ..Default::default()
+        };
+    }
+
+    pub fn record_win(&mut self, level: Level, record: Record) {
+        self.beaten.insert(level);
+        let old_best = self.records.get(&level).map_or(0, |best| best.score);
+        self.score += record.score.saturating_sub(old_best);
+
+        let best = self.records.entry(level).or_insert_with(|| record.clone());
+        best.found.extend(record.found);
+        best.score = best.score.max(record.score);
+        best.time = best.time.min(record.time);
+        best.peak_death_ball_size = best.peak_death_ball_size.max(record.peak_death_ball_size);
+        best.stars = best.stars.max(record.stars);
+    }
+
+    pub fn record_waves_survived(&mut self, level: Level, waves: u16) {
+        let best = self.most_waves_survived.entry(level).or_default();
+        *best = (*best).max(waves);
+    }
+}
+
+impl Record {
+    pub fn new(
+        score: u32,
+        max_score: u32,
+        time: f32,
+        peak_death_ball_size: u8,
+        found: BTreeSet<AnimalVariant>,
+    ) -> Self {
+        Record {
+            score,
+            time,
+            peak_death_ball_size,
+            stars: grade(score, max_score),
+            found,
+        }
+    }
+}
+
+/// Grades a score with 1 to 3 stars, by how much of the max score is left
+fn grade(score: u32, max_score: u32) -> u8 {
+    if max_score == 0 {
+        return 3;
+    }
+    match score as f32 / max_score as f32 {
+        amount if amount >= 2. / 3. => 3,
+        amount if amount >= 1. / 3. => 2,
+        _ => 1,
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod storage {
+    use std::path::PathBuf;
+
+    use macroquad::prelude::*;
+
+    fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("deathball").join("save.ron"))
+    }
+
+    pub fn read() -> Option<String> {
+        std::fs::read_to_string(path()?).ok()
+    }
+
+    pub fn write(source: &str) {
+        let path = match path() {
+            Some(path) => path,
+            None => return error!("Failed to save: no data directory"),
+        };
+        let result = std::fs::create_dir_all(path.parent().unwrap())
+            .and_then(|_| std::fs::write(&path, source));
+        if let Err(err) = result {
+            error!("Failed to save to {}: {}", path.display(), err);
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod storage {
+    // see index.html
+    #[wasm_bindgen::prelude::wasm_bindgen]
+    extern "C" {
+        fn load_save() -> Option<String>;
+        fn store_save(source: &str);
+    }
+
+    pub fn read() -> Option<String> {
+        load_save()
+    }
+
+    pub fn write(source: &str) {
+        store_save(source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_keep_the_best_of_each_record_per_level() {
+        let mut save = Save::default();
+        save.record_win(
+            Level::Scenario1,
+            Record::new(100, 300, 60., 10, BTreeSet::new()),
+        );
+        save.record_win(
+            Level::Scenario1,
+            Record::new(250, 300, 90., 5, BTreeSet::new()),
+        );
+        save.record_win(
+            Level::Scenario1,
+            Record::new(200, 300, 30., 20, BTreeSet::new()),
+        );
+
+        let record = &save.records[&Level::Scenario1];
+        assert_eq!(record.score, 250);
+        assert_eq!(record.time, 30.);
+        assert_eq!(record.peak_death_ball_size, 20);
+        assert_eq!(record.stars, 3);
+
+        assert_eq!(save.score, 250);
+        assert!(save.beaten.contains(&Level::Scenario1));
+    }
+
+    #[test]
+    fn should_only_add_score_improvements_to_the_total() {
+        let mut save = Save::default();
+        save.record_win(
+            Level::Tutorial,
+            Record::new(100, 100, 1., 1, BTreeSet::new()),
+        );
+        save.record_win(
+            Level::Scenario1,
+            Record::new(200, 300, 60., 10, BTreeSet::new()),
+        );
+        assert_eq!(save.score, 300);
+
+        save.record_win(
+            Level::Scenario1,
+            Record::new(200, 300, 60., 10, BTreeSet::new()),
+        );
+        save.record_win(
+            Level::Scenario1,
+            Record::new(50, 300, 60., 10, BTreeSet::new()),
+        );
+        assert_eq!(save.score, 300, "replaying without beating the best");
+
+        save.record_win(
+            Level::Scenario1,
+            Record::new(260, 300, 60., 10, BTreeSet::new()),
+        );
+        assert_eq!(save.score, 360);
+    }
+
+    #[test]
+    fn should_keep_every_animal_found_per_level() {
+        let mut save = Save::default();
+        let found = |variants: &[AnimalVariant]| variants.iter().copied().collect();
+        let first = found(&[AnimalVariant::Cat, AnimalVariant::Dog]);
+        save.record_win(Level::Scenario1, Record::new(100, 300, 60., 10, first));
+        let second = found(&[AnimalVariant::Dog, AnimalVariant::RubberDucky]);
+        save.record_win(Level::Scenario1, Record::new(100, 300, 60., 10, second));
+
+        let expected: BTreeSet<_> = found(&[
+            AnimalVariant::Cat,
+            AnimalVariant::Dog,
+            AnimalVariant::RubberDucky,
+        ]);
+        assert_eq!(save.records[&Level::Scenario1].found, expected);
+    }
+
+    #[test]
+    fn should_grade_relative_to_the_max_score() {
+        assert_eq!(grade(0, 30000), 1);
+        assert_eq!(grade(9999, 30000), 1);
+        assert_eq!(grade(10000, 30000), 2);
+        assert_eq!(grade(19999, 30000), 2);
+        assert_eq!(grade(20000, 30000), 3);
+        assert_eq!(grade(30000, 30000), 3);
+        assert_eq!(grade(0, 0), 3);
+    }
+
+    #[test]
+    fn should_keep_settings_when_resetting_progress() {
+        let mut save = Save::default();
+        save.settings.bgm_volume = 0.1;
+        save.record_win(
+            Level::Tutorial,
+            Record::new(100, 100, 1., 1, BTreeSet::new()),
+        );
+        save.reset_progress();
+        assert!(!save.has_progress());
+        assert_eq!(save.score, 0);
+        assert_eq!(save.settings.bgm_volume, 0.1);
+    }
+
+    #[test]
+    fn should_round_trip_through_ron() {
+        let mut save = Save::default();
+        save.record_win(
+            Level::Final,
+            Record::new(1234, 60000, 123.5, 42, BTreeSet::new()),
+        );
+        let save = Save::parse(&ron::to_string(&save).unwrap()).unwrap();
+        assert!(save.beaten.contains(&Level::Final));
+        assert_eq!(save.records[&Level::Final].score, 1234);
+        assert_eq!(save.records[&Level::Final].time, 123.5);
+    }
+
+    #[test]
+    fn should_default_missing_fields() {
+        let save = Save::parse("(score: 5)").unwrap();
+        assert_eq!(save.score, 5);
+        assert!(save.beaten.is_empty());
+        assert_eq!(save.settings.sfx_volume, Settings::default().sfx_volume);
+    }
+}
@@ -1,278 +1,411 @@
 use macroquad::prelude::*;
 
 use crate::{
-    animals::Animal,
     audio::bgm,
     background::Background,
-    buildings::Building,
-    camera::Camera,
-    death_ball::DeathBall,
-    enemies::Enemy,
+    camera::{Camera, Follow},
+    debug::Command,
     entities::Entities,
     groups,
     hit_effect::HitEffect,
-    levels::Level,
+    levels::{Level, LevelDefinition},
+    minimap::Minimap,
     objectives::Objective,
-    physics::{PhysicsEvent, PhysicsEventKind},
+    replay::{Frame, Replay},
+    rng::Rng,
+    save::Record,
     scenes,
+    simulation::{Cheat, Event, Simulation, Status},
     text_bubbles::TextBubble,
     Resources,
 };
 
 use super::{Scene, SceneChange};
 
-const PAN_SPEED: f32 = 15.;
-
 const INITIAL_ZOOM: f32 = 0.0015;
-const ZOOM_FACTOR: f32 = 1.05;
-const MIN_ZOOM: f32 = 0.00035;
-const MAX_ZOOM: f32 = 0.005;
-
-const LOSE_TIME: f32 = 5.;
-
-#[derive(PartialEq)]
-enum Status {
-    Playing,
-    Losing { timer: f32 },
-    HasLost,
-    HasWon,
-}
+
+const BOSS_BAR_WIDTH: f32 = 480.;
+/// Per level of nesting in the objective list
+const OBJECTIVE_INDENT: f32 = 16.;
+
+const ZONE_COLOR: Color = Color::new(1., 1., 1., 0.15);
+const ZONE_THICKNESS: f32 = 8.;
+
+/// Hit effects scattered over the inner part of an explosion
+const EXPLOSION_EFFECTS: usize = 8;
+
+/// In real seconds
+const BOSS_SLOW_MOTION_TIME: f32 = 2.;
+const CHAIN_SLOW_MOTION_TIME: f32 = 1.;
+/// Explosions this close together (in level time) count as a chain reaction
+const CHAIN_EXPLOSIONS: usize = 3;
+const CHAIN_TIME: f32 = 0.5;
+
+/// Added to the camera's trauma, see `Camera::add_trauma`
+const KILL_TRAUMA: f32 = 0.15;
+const EXPLOSION_TRAUMA: f32 = 0.35;
+const BOSS_KILL_TRAUMA: f32 = 0.8;
+
+/// In pixels per frame, so it moves at the same speed on screen whatever the zoom
+const CURSOR_SPEED: f32 = 12.;
+const CURSOR_RADIUS: f32 = 48.;
+const CURSOR_THICKNESS: f32 = 8.;
 
 pub struct Combat {
     camera: Camera,
-    level: Level,
+    /// Whether the camera follows the deathball (if enabled in the settings), which stops once
+    /// the player takes over by panning, until the camera is reset
+    is_following: bool,
     bgm: bgm::Track,
-    objective: Objective,
     background: Background,
+    minimap: Minimap,
     text_bubbles: Vec<TextBubble>,
-    death_ball: DeathBall,
-    animals: Entities<Animal, { groups::ANIMAL }>,
-    buildings: Entities<Building, { groups::BUILDING }>,
-    enemies: Entities<Enemy, { groups::ENEMY }>,
+    simulation: Simulation,
+    /// Where the deathball is being steered to with a stick, in world coordinates
+    cursor: Option<Vec2>,
     hit_effects: Entities<HitEffect, { groups::HIT_EFFECT }>,
-    death_ball_size: u8,
-    score: f32,
-    status: Status,
+    /// When each of the latest explosions went off, to spot chain reactions
+    explosions: Vec<f32>,
+    replay: Replay,
+    /// The next frame of the replay to play back, or None if it's being recorded instead
+    playback: Option<usize>,
+    /// Started from the editor, which it returns to instead of moving on to other scenes
+    is_playtest: bool,
+    /// From the last UI update, so that clicks meant for the UI are ignored
+    is_ui_focused: bool,
 }
 
 impl Combat {
     pub fn boxed(res: &mut Resources, level: Level) -> Box<Self> {
-        res.physics.reset();
-        let data = level.init(res);
-        let death_ball = DeathBall::new(res, Vec2::ZERO);
-        let hit_effects = Entities::<HitEffect, { groups::HIT_EFFECT }>::new();
+        let replay = Replay::new(level, res.seed);
+        Box::new(Combat::new(res, replay, None))
+    }
+
+    pub fn playback(res: &mut Resources, replay: Replay) -> Box<Self> {
+        Box::new(Combat::new(res, replay, Some(0)))
+    }
+
+    /// Plays a level from the editor, returning to it once over
+    pub fn playtest(res: &mut Resources, definition: &LevelDefinition) -> Box<Self> {
+        let replay = Replay::new(Level::Custom, res.seed);
+        let rng = Rng::new(replay.seed);
         Box::new(Combat {
-            camera: Camera::new(Vec2::ZERO, INITIAL_ZOOM),
-            level,
-            bgm: data.bgm,
-            objective: data.objective,
-            background: data.background,
-            text_bubbles: data.text_bubbles,
-            animals: data.animals,
-            buildings: data.buildings,
-            enemies: data.enemies,
-            death_ball,
-            hit_effects,
-            death_ball_size: 0,
-            score: data.max_score as f32,
-            status: Status::Playing,
+            is_playtest: true,
+            ..Combat::from_definition(definition, replay, None, rng)
         })
     }
-}
 
-impl Combat {
-    fn update_death_ball_size(&mut self) {
-        self.death_ball_size = self
-            .animals
-            .into_iter()
-            .filter(|a| a.is_affected_by_death_ball)
-            .count() as u8;
+    fn new(res: &mut Resources, replay: Replay, playback: Option<usize>) -> Self {
+        // the level (ie. the test level) may be randomized too, so the stream starts here
+        let mut rng = Rng::new(replay.seed);
+        let definition = (replay.level.definition(&res.assets, &mut rng))
+            .expect("custom levels are only played through `playtest`");
+        Combat::from_definition(&definition, replay, playback, rng)
+    }
+
+    fn from_definition(
+        definition: &LevelDefinition,
+        replay: Replay,
+        playback: Option<usize>,
+        rng: Rng,
+    ) -> Self {
+        let background = definition.background();
+        Combat {
+            camera: Camera::new(Vec2::ZERO, INITIAL_ZOOM).with_bounds(Some(definition.bounds())),
+            is_following: true,
+            bgm: definition.bgm,
+            minimap: Minimap::new(definition.bounds()),
+            background,
+            text_bubbles: definition.text_bubbles(),
+            simulation: Simulation::new(replay.level, definition, rng),
+            cursor: None,
+            hit_effects: Entities::new(),
+            explosions: Vec::new(),
+            replay,
+            playback,
+            is_playtest: false,
+            is_ui_focused: false,
+        }
+    }
+
+    fn is_playback_finished(&self) -> bool {
+        matches!(self.playback, Some(next) if next >= self.replay.frames.len())
     }
 }
 
 impl Scene for Combat {
     fn on_enter(&mut self, res: &mut Resources) {
         res.audio.bgm.play(self.bgm);
+        res.time_scale.reset();
     }
 
     fn update(&mut self, res: &mut Resources) -> SceneChange {
-        // Update camera
-        {
-            // Mouse Panning
-            if let Some(drag) = res.input.pan_camera_mouse_drag() {
-                let previous = self.camera.screen_to_world(drag.previous);
-                let current = self.camera.screen_to_world(drag.current);
-                self.camera.target += previous - current;
-            }
-            // WASD Panning
-            else {
-                self.camera.target += res.input.pan_camera_keyboard() * PAN_SPEED;
-            }
-
-            // Mouse Zoom
-            if let Some(amount) = res.input.zoom_camera() {
-                self.camera.zoom =
-                    (self.camera.zoom * ZOOM_FACTOR.powf(amount)).clamp(MIN_ZOOM, MAX_ZOOM);
-            }
-
-            if res.input.reset_camera() {
-                self.camera.zoom = INITIAL_ZOOM;
-                self.camera.target = self.death_ball.get_position(res);
-            }
-        }
-
-        // Update entities
-        self.death_ball.update(res, &self.camera);
-        for animal in &mut self.animals {
-            animal.update(res, &self.death_ball);
-        }
-        for building in &mut self.buildings {
-            building.update(res, &mut self.animals);
+        // replays can't be paused, as the pause menu can leave to other scenes
+        if self.is_playtest && res.input.pause() {
+            return SceneChange::Pop;
         }
-        for enemy in &mut self.enemies {
-            enemy.update(res);
-        }
-        for hit_effect in &mut self.hit_effects {
-            hit_effect.update(res);
+        if self.playback.is_none() && res.input.pause() {
+            let sim = &self.simulation;
+            let waves_survived = (!sim.spawners.is_empty()).then(|| sim.waves_survived());
+            return SceneChange::Push(scenes::Pause::boxed(sim.level, waves_survived));
         }
 
-        // Clear deleted entities
-        for idx in res.deleted.drain(..) {
-            match idx.group() {
-                groups::ANIMAL => self.animals.remove(idx),
-                groups::BUILDING => self.buildings.remove(idx),
-                groups::ENEMY => self.enemies.remove(idx),
-                groups::HIT_EFFECT => self.hit_effects.remove(idx),
-                _ => {}
-            };
-        }
+        let delta = res.time_scale.update(res.delta);
 
-        // handle status changes
-        match self.status {
-            Status::Playing | Status::Losing { .. } if self.objective.is_complete() => {
-                self.status = Status::HasWon;
-            }
-            Status::Playing if self.death_ball_size == 0 => {
-                self.status = Status::Losing { timer: LOSE_TIME };
-            }
-            Status::Losing { .. } if self.death_ball_size > 0 => {
-                self.status = Status::Playing;
-            }
-            Status::Losing { ref mut timer } => {
-                *timer -= res.delta;
-                if *timer < 0. {
-                    self.status = Status::HasLost;
+        // Update simulation, either from live input (recording it) or from the replay
+        let minimap_click = res
+            .input
+            .move_deathball()
+            .filter(|&point| !self.is_ui_focused && self.minimap.contains(point));
+
+        let mut frame = Frame::from_input(&res.input, &self.camera);
+        match self.playback {
+            Some(ref mut next) => {
+                if let Some(recorded) = self.replay.frames.get(*next) {
+                    frame = recorded.clone();
+                    *next += 1;
+                } else {
+                    // once finished only allow looking around
+                    frame.move_deathball = None;
+                }
+                for cheat in &frame.cheats {
+                    self.simulation.cheat(cheat);
+                }
+                for _ in 0..frame.steps {
+                    self.simulation.step(&frame.commands());
                 }
             }
-            _ => {}
-        }
+            None => {
+                // clicks on the minimap are for the camera
+                if minimap_click.is_some() {
+                    frame.move_deathball = None;
+                }
 
-        if !matches!(self.status, Status::HasWon) {
-            self.score = (self.score - res.delta * 100.).max(0.);
-        }
+                // steering starts from the deathball, and then moves the cursor for it to follow
+                let steer = res.input.steer_deathball();
+                if steer == Vec2::ZERO {
+                    self.cursor = None;
+                } else {
+                    let sim = &self.simulation;
+                    let cursor = self
+                        .cursor
+                        .get_or_insert_with(|| sim.death_ball.get_position(&sim.world));
+                    *cursor += steer * CURSOR_SPEED / (self.camera.zoom * screen_width() / 2.);
+                    frame.move_deathball = Some((*cursor).into());
+                }
 
-        // handle scene changing
-        if matches!(self.status, Status::HasLost) && res.input.go_to_next_scene() {
-            return SceneChange::Change(scenes::Combat::boxed(res, self.level));
-        }
-        if matches!(self.status, Status::HasWon) && res.input.go_to_next_scene() {
-            res.beaten.insert(self.level);
-            res.score += self.score.floor() as u32;
-            return SceneChange::Change(scenes::LevelSelect::boxed(res));
-        }
+                let is_over = matches!(self.simulation.status, Status::HasLost | Status::HasWon);
+                if !is_over && res.input.fast_forward() {
+                    res.time_scale.is_fast_forward = !res.time_scale.is_fast_forward;
+                }
 
-        SceneChange::None
-    }
+                for command in res.debug.drain_commands() {
+                    frame.cheats.push(match command {
+                        Command::Spawn(spawnable) => {
+                            let position = self.camera.screen_to_world(mouse_position().into());
+                            Cheat::Spawn(spawnable, position.into())
+                        }
+                        Command::KillEnemies => Cheat::KillEnemies,
+                        Command::SetDeathBallSize(size) => Cheat::SetDeathBallSize(size),
+                    });
+                }
+                if res.debug.auto_complete_objectives && !self.simulation.objective.is_complete() {
+                    frame.cheats.push(Cheat::CompleteObjective);
+                }
+                for cheat in &frame.cheats {
+                    self.simulation.cheat(cheat);
+                }
 
-    fn handle_physics_event(&mut self, res: &mut Resources, event: PhysicsEvent) {
-        let idx1 = res.physics.get_idx(event.collider1);
-        let idx2 = res.physics.get_idx(event.collider2);
+                frame.steps = if res.time_scale.is_stepping {
+                    let steps = res.time_scale.take_steps();
+                    for _ in 0..steps {
+                        self.simulation.step(&frame.commands());
+                    }
+                    steps
+                } else {
+                    self.simulation.update(delta, &frame.commands())
+                };
+                self.replay.frames.push(frame.clone());
+
+                // custom levels aren't around to be played back
+                if !self.is_playtest && res.input.save_replay() {
+                    match self.replay.save() {
+                        Ok(path) => info!("Saved replay to {}", path.display()),
+                        Err(err) => error!("Failed to save replay: {}", err),
+                    }
+                }
+            }
+        }
 
-        // DeathBall with Animal
-        if idx1 == groups::DEATH_BALL && idx2.group() == groups::ANIMAL {
-            let animal = &mut self.animals[idx2];
-            animal.is_affected_by_death_ball = true;
+        // Update camera
+        {
+            let follow = res.save.settings.camera_follow;
+            let pan: Vec2 = frame.pan_camera.into();
+            let is_zooming = frame.zoom_camera.is_some();
+            if frame.pan_camera_mouse_drag().is_some()
+                || pan != Vec2::ZERO
+                || (follow == Follow::Animals && is_zooming)
+            {
+                self.is_following = false;
+            }
 
-            self.update_death_ball_size();
-            self.objective
-                .on_update_death_ball_count(self.death_ball_size);
-            return;
-        }
+            self.camera
+                .pan_and_zoom(frame.pan_camera_mouse_drag(), pan, frame.zoom_camera);
 
-        // Animal with Building
-        if idx1.group() == groups::ANIMAL && idx2.group() == groups::BUILDING {
-            let animal = &mut self.animals[idx1];
-            let building = &mut self.buildings[idx2];
+            let sim = &self.simulation;
+            let position = sim.death_ball.get_position(&sim.world);
+            if frame.reset_camera {
+                self.camera.zoom = INITIAL_ZOOM;
+                self.camera.target = position;
+                self.is_following = true;
+            }
 
-            if let PhysicsEventKind::ContactStart { point } = event.kind {
-                let just_destroyed = building.damage(animal.damage);
-                if just_destroyed {
-                    self.objective.on_destroy_building();
-                    res.audio.killed_sfx.play();
-                } else {
-                    res.audio.hit_sfx.play();
+            if self.is_following {
+                match follow {
+                    Follow::Off => {}
+                    Follow::DeathBall => self.camera.follow(res.delta, position, None),
+                    Follow::Animals => {
+                        let area = death_ball_area(sim);
+                        let position =
+                            area.map_or(position, |area| area.point() + area.size() / 2.);
+                        self.camera.follow(res.delta, position, area);
+                    }
                 }
-
-                // spawn hit effects on contact
-                self.hit_effects.push(|idx| HitEffect::new(idx, point));
             }
 
-            return;
+            if let Some(point) = minimap_click {
+                self.camera.target = self.minimap.to_world(point);
+                self.is_following = false;
+            }
         }
 
-        // Animal with Enemy
-        if idx1.group() == groups::ANIMAL && idx2.group() == groups::ENEMY {
-            let animal = &mut self.animals[idx1];
-            let enemy = &mut self.enemies[idx2];
-
-            match event.kind {
-                // will only happen for detection range sensor
-                PhysicsEventKind::IntersectStart => enemy.add_nearby(event.collider1),
-                PhysicsEventKind::IntersectEnd => enemy.remove_nearby(event.collider1),
-
-                // will only happen for collision body
-                PhysicsEventKind::ContactStart { point } => {
-                    let just_killed = enemy.damage(animal.damage);
-                    if just_killed {
-                        self.objective.on_kill_enemy();
-                        res.audio.killed_sfx.play();
-                    } else {
-                        res.audio.hit_sfx.play();
+        // React to what happened in the simulation
+        let time = self.simulation.time;
+        let mut trauma = 0.;
+        for event in self.simulation.drain_events() {
+            let (point, spread, count) = match event {
+                Event::Hit { point } => {
+                    res.audio.hit_sfx.play(&mut res.rng);
+                    (point, 0., 1)
+                }
+                Event::Killed { point } => {
+                    res.audio.killed_sfx.play(&mut res.rng);
+                    trauma += KILL_TRAUMA;
+                    (point, 0., 1)
+                }
+                Event::AnimalKilled { point } => {
+                    res.audio.killed_sfx.play(&mut res.rng);
+                    (point, 0., 1)
+                }
+                Event::BossKilled => {
+                    res.time_scale.slow_motion(BOSS_SLOW_MOTION_TIME);
+                    trauma += BOSS_KILL_TRAUMA;
+                    continue;
+                }
+                Event::Exploded { point, radius } => {
+                    res.audio.killed_sfx.play(&mut res.rng);
+                    trauma += EXPLOSION_TRAUMA;
+
+                    self.explosions.retain(|&other| time - other <= CHAIN_TIME);
+                    self.explosions.push(time);
+                    if self.explosions.len() >= CHAIN_EXPLOSIONS {
+                        res.time_scale.slow_motion(CHAIN_SLOW_MOTION_TIME);
                     }
 
-                    // spawn hit effects on contact
-                    self.hit_effects.push(|idx| HitEffect::new(idx, point));
+                    (point, radius / 2., EXPLOSION_EFFECTS)
                 }
-                _ => {}
-            }
+            };
 
-            return;
+            // spawn hit effects on contact
+            for _ in 0..count {
+                let angle = res.rng.gen_range(0., std::f32::consts::TAU);
+                let distance = res.rng.gen_range(0., 1.) * spread;
+                let point = point + vec2(angle.cos(), angle.sin()) * distance;
+                self.hit_effects
+                    .push(|idx| HitEffect::new(idx, &mut res.rng, point));
+            }
         }
 
-        // Animal with Enemy Attacks
-        if idx1.group() == groups::ANIMAL && idx2.group() == groups::ENEMY_ATTACK {
-            let animal = &mut self.animals[idx1];
-            let animal_handle = event.collider1;
-            let enemy = &self.enemies[idx2.with_group(groups::ENEMY)];
-            let enemy_handle = event.collider2;
-
-            let animal_pos = res.physics.get_position(animal_handle);
-            let enemy_pos = res.physics.get_position(enemy_handle);
-            let direction = (animal_pos - enemy_pos).normalize_or_zero();
+        if res.save.settings.screen_shake {
+            self.camera.add_trauma(trauma);
+        }
+        self.camera.update(res.delta, &mut res.rng);
 
-            animal.is_affected_by_death_ball = false;
-            res.physics
-                .apply_impulse(animal_handle, direction * enemy.attack_impulse);
+        // Update effects
+        let mut finished = Vec::new();
+        for hit_effect in &mut self.hit_effects {
+            hit_effect.update(delta, &mut res.rng, &mut finished);
+        }
+        for idx in finished {
+            self.hit_effects.remove(idx);
+        }
 
-            self.update_death_ball_size();
-            self.objective
-                .on_update_death_ball_count(self.death_ball_size);
+        // handle scene changing
+        let sim = &self.simulation;
+        if self.playback.is_some() {
+            // the viewer decides when to leave, not the recording
+            let is_over = matches!(sim.status, Status::HasLost | Status::HasWon);
+            if (is_over || self.is_playback_finished()) && res.input.go_to_next_scene() {
+                return SceneChange::Change(scenes::MainMenu::boxed());
+            }
+            return SceneChange::None;
+        }
+        if self.is_playtest {
+            let is_over = matches!(sim.status, Status::HasLost | Status::HasWon);
+            if is_over && frame.go_to_next_scene {
+                return SceneChange::Pop;
+            }
+            return SceneChange::None;
+        }
+        if matches!(sim.status, Status::HasLost) && frame.go_to_next_scene {
+            if !sim.spawners.is_empty() {
+                res.save
+                    .record_waves_survived(sim.level, sim.waves_survived());
+                res.save.store();
+            }
+            return SceneChange::Change(scenes::Combat::boxed(res, sim.level));
+        }
+        if matches!(sim.status, Status::HasWon) && frame.go_to_next_scene {
+            let score = sim.score.floor() as u32;
+            let found = sim.drops.found().keys().copied().collect();
+            let record = Record::new(
+                score,
+                sim.max_score,
+                sim.time,
+                sim.peak_death_ball_size,
+                found,
+            );
+            res.save.record_win(sim.level, record);
+            res.save.store();
+            return SceneChange::Change(scenes::LevelSelect::boxed(res));
         }
+
+        SceneChange::None
     }
 
-    fn update_ui(&mut self, _res: &mut Resources, ctx: &egui::CtxRef) -> SceneChange {
+    fn update_ui(&mut self, res: &mut Resources, ctx: &egui::CtxRef) -> SceneChange {
         use egui::*;
 
+        self.is_ui_focused = ctx.is_pointer_over_area();
+        let sim = &self.simulation;
+
+        let is_over = matches!(sim.status, Status::HasLost | Status::HasWon);
+        if self.playback.is_none() && !is_over {
+            Window::new("speed")
+                .title_bar(false)
+                .resizable(false)
+                .anchor(egui::Align2::RIGHT_TOP, (-8., 8.))
+                .show(ctx, |ui| {
+                    let time_scale = &mut res.time_scale;
+                    if ui
+                        .selectable_label(time_scale.is_fast_forward, "Fast Forward")
+                        .clicked()
+                    {
+                        time_scale.is_fast_forward = !time_scale.is_fast_forward;
+                    }
+                });
+        }
+
         Window::new("score")
             .title_bar(false)
             .resizable(false)
@@ -284,7 +417,7 @@ impl Scene for Combat {
                             ui.label("Score:");
                         });
                         cols[1].with_layout(Layout::top_down(Align::Center), |ui| {
-                            ui.label(format!("{:.2}", self.score as f32 / 100.));
+                            ui.label(format!("{:.2}", sim.score / 100.));
                         });
                     });
                 });
@@ -294,14 +427,45 @@ impl Scene for Combat {
             .anchor(egui::Align2::CENTER_TOP, (0., 96.))
             .show(ctx, |ui| {
                 ui.with_layout(Layout::top_down(Align::Center), |ui| {
-                    if let Status::HasLost = self.status {
+                    if self.playback.is_some() {
+                        if self.is_playback_finished() {
+                            ui.label(RichText::new("Replay Finished").heading());
+                            ui.allocate_exact_size(vec2(0., 8.), Sense::hover());
+                        }
+                        let is_over = matches!(sim.status, Status::HasLost | Status::HasWon);
+                        if self.is_playback_finished() || is_over {
+                            ui.label("Press Spacebar to return to the main menu.");
+                        }
+                    } else if let Status::HasLost = sim.status {
                         ui.label(RichText::new("You Lose!").heading());
                         ui.allocate_exact_size(vec2(0., 8.), Sense::hover());
-                        ui.label("Press Spacebar to retry.");
-                    } else if let Status::HasWon = self.status {
+                        if !sim.spawners.is_empty() {
+                            ui.label(format!("Waves Survived: {}", sim.waves_survived()));
+                            ui.allocate_exact_size(vec2(0., 8.), Sense::hover());
+                        }
+                        if self.is_playtest {
+                            ui.label("Press Spacebar to return to the editor.");
+                        } else {
+                            ui.label("Press Spacebar to retry.");
+                        }
+                    } else if let Status::HasWon = sim.status {
                         ui.label(RichText::new("You Win!").heading());
                         ui.allocate_exact_size(vec2(0., 8.), Sense::hover());
-                        ui.label("Press Spacebar to go to next screen.");
+                        if !sim.drops.found().is_empty() {
+                            let found: Vec<_> = sim
+                                .drops
+                                .found()
+                                .iter()
+                                .map(|(variant, count)| format!("{:?} x{}", variant, count))
+                                .collect();
+                            ui.label(format!("Animals found: {}", found.join(", ")));
+                            ui.allocate_exact_size(vec2(0., 8.), Sense::hover());
+                        }
+                        if self.is_playtest {
+                            ui.label("Press Spacebar to return to the editor.");
+                        } else {
+                            ui.label("Press Spacebar to go to next screen.");
+                        }
                     }
                 });
             });
@@ -315,23 +479,62 @@ impl Scene for Combat {
                     cols[0].label("Objective:");
                     cols[0].label("Current:");
 
-                    cols[1].add(Label::new(self.objective.to_string()).wrap(false));
-                    cols[1].label(self.objective.current().to_string());
+                    cols[1].add(Label::new(sim.objective.to_string()).wrap(false));
+                    cols[1].label(sim.objective.current().to_string());
+
+                    if let Some(time_left) = sim.objective.time_left(sim.time) {
+                        cols[0].label("Time Left:");
+                        cols[1].label(format!("{:.0}s", time_left.ceil()));
+                    }
+
+                    if !sim.spawners.is_empty() {
+                        cols[0].label("Wave:");
+                        cols[1].label((sim.waves_survived() + 1).to_string());
+                    }
                 });
+
+                for objective in sim.objective.objectives() {
+                    objective_rows(ui, objective, 0);
+                }
+                for bonus in sim.objective.bonus() {
+                    let score = bonus.score as f32 / 100.;
+                    ui.add(Label::new(format!("Bonus (+{:.2}):", score)).wrap(false));
+                    objective_rows(ui, &bonus.objective, 1);
+                }
             });
 
+        let bosses: Vec<_> = sim
+            .enemies
+            .into_iter()
+            .filter_map(|enemy| enemy.boss_status())
+            .collect();
+        if !bosses.is_empty() {
+            Window::new("bosses")
+                .title_bar(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_BOTTOM, (0., -8.))
+                .show(ctx, |ui| {
+                    for boss in bosses {
+                        ui.with_layout(Layout::top_down(Align::Center), |ui| {
+                            ui.label(format!("{} - Phase {}", boss.name, boss.phase));
+                        });
+                        ui.add(ProgressBar::new(boss.health).desired_width(BOSS_BAR_WIDTH));
+                    }
+                });
+        }
+
         Window::new("size")
             .title_bar(false)
             .resizable(false)
             .anchor(egui::Align2::RIGHT_BOTTOM, (-8., -8.))
             .show(ctx, |ui| {
                 ui.with_layout(Layout::top_down(Align::Center), |ui| {
-                    if let Status::Losing { timer } = self.status {
+                    if let Status::Losing { timer } = sim.status {
                         ui.label("Deathball Is Dissolved");
                         ui.label(timer.ceil().to_string());
                     } else {
                         ui.label("Deathball Count");
-                        ui.label(self.death_ball_size.to_string());
+                        ui.label(sim.death_ball_size.to_string());
                     }
                 });
             });
@@ -347,24 +550,84 @@ impl Scene for Combat {
             text_bubble.draw(res);
         }
 
-        self.death_ball.draw(res);
+        let sim = &self.simulation;
+        for (position, radius) in sim.objective.zones() {
+            let (x, y) = position.into();
+            draw_circle(x, y, radius, ZONE_COLOR);
+            draw_circle_lines(x, y, radius, ZONE_THICKNESS, ZONE_COLOR);
+        }
+        sim.death_ball.draw(res, &sim.world);
+        for fire in &sim.fires {
+            fire.draw();
+        }
         for hit_effect in &self.hit_effects {
             hit_effect.draw();
         }
-        for animal in &self.animals {
-            animal.draw(res);
+        for animal in &sim.animals {
+            animal.draw(res, &sim.world);
+        }
+        for enemy in &sim.enemies {
+            enemy.draw(res, &sim.world);
         }
-        for enemy in &self.enemies {
-            enemy.draw(res);
+        for building in &sim.buildings {
+            building.draw(res, &sim.world);
         }
-        for building in &self.buildings {
-            building.draw(res);
+        for debris in &sim.debris {
+            debris.draw(res, &sim.world);
+        }
+        for projectile in &sim.projectiles {
+            projectile.draw(&sim.world);
         }
 
-        if crate::debug::DRAW_COLLIDERS {
-            res.physics.draw_colliders();
+        if let Some(cursor) = self.cursor {
+            draw_circle_lines(cursor.x, cursor.y, CURSOR_RADIUS, CURSOR_THICKNESS, WHITE);
+        }
+
+        if res.debug.draw_colliders {
+            sim.world.physics.draw_colliders();
         }
 
         self.camera.disable();
+
+        self.minimap.draw(sim, &self.camera);
+    }
+}
+
+/// Around every animal in the deathball, or None if it's empty
+fn death_ball_area(sim: &Simulation) -> Option<Rect> {
+    let mut positions = sim
+        .animals
+        .into_iter()
+        .filter(|animal| animal.is_affected_by_death_ball)
+        .map(|animal| animal.get_position(&sim.world));
+    let first = positions.next()?;
+    let (min, max) = positions.fold((first, first), |(min, max), position| {
+        (min.min(position), max.max(position))
+    });
+    Some(Rect::new(min.x, min.y, max.x - min.x, max.y - min.y))
+}
+
+/// Lists the objective with its progress, followed by any objectives nested in it
+fn objective_rows(ui: &mut egui::Ui, objective: &Objective, depth: usize) {
+    let mark = if objective.is_complete() {
+        "✔"
+    } else if objective.is_failed() {
+        "✖"
+    } else {
+        "•"
+    };
+    let text = format!(
+        "{} {} ({}/{})",
+        mark,
+        objective,
+        objective.current(),
+        objective.target()
+    );
+    ui.horizontal(|ui| {
+        ui.add_space(OBJECTIVE_INDENT * depth as f32);
+        ui.add(egui::Label::new(text).wrap(false));
+    });
+    for objective in objective.objectives() {
+        objective_rows(ui, objective, depth + 1);
     }
 }
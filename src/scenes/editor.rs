@@ -0,0 +1,618 @@
+//! For making levels in game, rather than in Godot, see `Debug::enable_editor`.
+//!
+//! Left click places whatever's picked in the editor window, or grabs what's already there to drag
+//! it around. Delete removes what's selected. Everything else about the level that can't be placed
+//! (eg. spawners and drops) is kept as loaded. Pausing leaves for the main menu, asking first if
+//! there are unsaved changes.
+
+use macroquad::prelude::*;
+
+use crate::{
+    animals::{Animal, Variant as AnimalVariant},
+    audio::bgm,
+    background::{Background, Prop},
+    buildings::{Building, Variant as BuildingVariant},
+    camera::Camera,
+    enemies::{Enemy, Variant as EnemyVariant},
+    levels::{BackgroundDefinition, Level, LevelDefinition, TextBubbleDefinition, LEVEL_FILES},
+    objectives::Objective,
+    rng::Rng,
+    scenes,
+    simulation::Simulation,
+    text_bubbles::TextBubble,
+    Resources,
+};
+
+use super::{Scene, SceneChange};
+
+const INITIAL_ZOOM: f32 = 0.001;
+
+const DEFAULT_PATH: &str = "./assets/levels/custom.ron";
+const DEFAULT_MAX_SCORE: u32 = 30000;
+const DEFAULT_CLEAR_COLOR: (f32, f32, f32, f32) = (0.23, 0.39, 0.15, 1.);
+
+/// How close a click needs to be to grab something, in world units
+const PICK_RADIUS: f32 = 64.;
+const SELECTED_COLOR: Color = Color::new(1., 1., 0., 0.8);
+const SELECTED_THICKNESS: f32 = 6.;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Tool {
+    Building(BuildingVariant),
+    Animal(AnimalVariant),
+    Enemy(EnemyVariant),
+    Prop(Prop),
+    TextBubble,
+}
+
+/// Indexes into the lists of the level definition
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Item {
+    Building(usize),
+    Animal(usize),
+    Enemy(usize),
+    Prop(usize),
+    TextBubble(usize),
+}
+
+pub struct Editor {
+    camera: Camera,
+    definition: LevelDefinition,
+    /// Rebuilt whenever the definition changes, to draw entities the same way as in `Combat`
+    preview: Simulation,
+    background: Background,
+    text_bubbles: Vec<TextBubble>,
+    tool: Tool,
+    selected: Option<Item>,
+    /// From the cursor to the selected item, while it's being dragged around
+    grab_offset: Option<Vec2>,
+    /// The preview is only rebuilt once the dragged item is let go of, as rebuilding is slow
+    is_dragged: bool,
+    snap_to_grid: bool,
+    draw_colliders: bool,
+    path: String,
+    /// The objective as RON, as it's too nested to give each part its own widget
+    objective_source: String,
+    /// The result of the last save, or why the objective couldn't be parsed
+    message: Option<String>,
+    /// From the last UI update, so that clicks and key presses meant for the UI are ignored
+    is_ui_focused: bool,
+    /// Since the level was loaded or last saved
+    has_unsaved_changes: bool,
+    /// Asking whether to leave for the main menu, as there are unsaved changes
+    is_confirming_leave: bool,
+}
+
+impl Editor {
+    pub fn boxed() -> Box<Self> {
+        let definition = LevelDefinition {
+            bgm: bgm::MeadowMeadow,
+            max_score: DEFAULT_MAX_SCORE,
+            objective: Objective::none(),
+            background: BackgroundDefinition {
+                clear_color: DEFAULT_CLEAR_COLOR,
+                offset: (0., 0.),
+                props: vec![],
+            },
+            text_bubbles: vec![],
+            // the deathball needs something to start with
+            animals: vec![(AnimalVariant::Horse, (0.00001, 0.))],
+            buildings: vec![],
+            enemies: vec![],
+            tagged_buildings: vec![],
+            drops: Default::default(),
+            spawners: vec![],
+        };
+        Box::new(Editor::new(definition, DEFAULT_PATH.to_owned()))
+    }
+
+    fn new(definition: LevelDefinition, path: String) -> Self {
+        Editor {
+            camera: Camera::new(Vec2::ZERO, INITIAL_ZOOM),
+            preview: Simulation::new(Level::Custom, &definition, Rng::new(0)),
+            background: definition.background(),
+            text_bubbles: definition.text_bubbles(),
+            objective_source: objective_to_ron(&definition.objective),
+            definition,
+            tool: Tool::Building(BuildingVariant::Barn),
+            selected: None,
+            grab_offset: None,
+            is_dragged: false,
+            snap_to_grid: true,
+            draw_colliders: false,
+            path,
+            message: None,
+            is_ui_focused: false,
+            has_unsaved_changes: false,
+            is_confirming_leave: false,
+        }
+    }
+
+    fn rebuild(&mut self) {
+        self.preview = Simulation::new(Level::Custom, &self.definition, Rng::new(0));
+        self.background = self.definition.background();
+        self.text_bubbles = self.definition.text_bubbles();
+    }
+
+    fn place(&mut self, point: Vec2, tile_size: f32) -> Option<Item> {
+        let definition = &mut self.definition;
+        let position = point.into();
+        let item = match self.tool {
+            Tool::Building(variant) => {
+                definition.buildings.push((variant, position));
+                Item::Building(definition.buildings.len() - 1)
+            }
+            Tool::Animal(variant) => {
+                definition.animals.push((variant, position));
+                Item::Animal(definition.animals.len() - 1)
+            }
+            Tool::Enemy(variant) => {
+                definition.enemies.push((variant, position));
+                Item::Enemy(definition.enemies.len() - 1)
+            }
+            Tool::Prop(prop) => {
+                let tile = to_tile(definition, point, tile_size)?;
+                definition.background.props.push((tile, prop));
+                Item::Prop(definition.background.props.len() - 1)
+            }
+            Tool::TextBubble => {
+                definition.text_bubbles.push(TextBubbleDefinition {
+                    position,
+                    rows: vec!["...".to_owned()],
+                });
+                Item::TextBubble(definition.text_bubbles.len() - 1)
+            }
+        };
+        Some(item)
+    }
+
+    fn save(&self) -> Result<(), String> {
+        std::fs::write(&self.path, self.definition.to_ron()).map_err(|err| err.to_string())
+    }
+
+    /// Shows how saving went, returns whether or not it succeeded
+    fn save_with_message(&mut self) -> bool {
+        let result = self.save();
+        self.message = Some(match &result {
+            Ok(()) => format!("Saved to {}", self.path),
+            Err(err) => format!("Failed to save to {}: {}", self.path, err),
+        });
+        self.has_unsaved_changes &= result.is_err();
+        result.is_ok()
+    }
+}
+
+impl Scene for Editor {
+    fn on_enter(&mut self, res: &mut Resources) {
+        res.audio.bgm.play(self.definition.bgm);
+    }
+
+    fn update(&mut self, res: &mut Resources) -> SceneChange {
+        // escape also leaves text fields, which shouldn't throw the level away
+        if !self.is_ui_focused && res.input.pause() {
+            if !self.has_unsaved_changes {
+                return SceneChange::Change(scenes::MainMenu::boxed());
+            }
+            self.is_confirming_leave = !self.is_confirming_leave;
+        }
+
+        let zoom = res.input.zoom_camera().filter(|_| !self.is_ui_focused);
+        let drag = res.input.pan_camera_mouse_drag();
+        self.camera.pan_and_zoom(drag, res.input.pan_camera(), zoom);
+
+        let tile_size = res.assets.props.cell_size;
+        let snap_to_grid = self.snap_to_grid;
+        let snap = |point: Vec2| {
+            if snap_to_grid {
+                (point / tile_size).round() * tile_size
+            } else {
+                point
+            }
+        };
+
+        let mut is_changed = false;
+        let grab = res.input.editor_grab().filter(|_| !self.is_ui_focused);
+        if let Some(cursor) = grab.map(|position| self.camera.screen_to_world(position)) {
+            self.selected = match pick(&self.definition, cursor, tile_size) {
+                Some(item) => Some(item),
+                None => {
+                    is_changed = true;
+                    self.place(snap(cursor), tile_size)
+                }
+            };
+            self.grab_offset = self
+                .selected
+                .map(|item| position_of(&self.definition, item, tile_size) - cursor);
+        }
+        match res.input.editor_drag() {
+            Some(position) => {
+                if let (Some(offset), Some(item)) = (self.grab_offset, self.selected) {
+                    let point = snap(self.camera.screen_to_world(position) + offset);
+                    self.is_dragged |= move_item(&mut self.definition, item, point, tile_size);
+                }
+            }
+            None => {
+                self.grab_offset = None;
+                is_changed |= std::mem::take(&mut self.is_dragged);
+            }
+        }
+        if !self.is_ui_focused && res.input.editor_delete() {
+            if let Some(item) = self.selected.take() {
+                remove_item(&mut self.definition, item);
+                is_changed = true;
+            }
+        }
+
+        if is_changed {
+            self.rebuild();
+            self.has_unsaved_changes = true;
+        }
+        SceneChange::None
+    }
+
+    fn update_ui(&mut self, res: &mut Resources, ctx: &egui::CtxRef) -> SceneChange {
+        use egui::*;
+
+        let mut scene_change = SceneChange::None;
+        let mut is_changed = false;
+
+        Window::new("Editor")
+            .resizable(false)
+            .anchor(egui::Align2::LEFT_TOP, (8., 8.))
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let mut load = None;
+                    ComboBox::from_id_source("load")
+                        .selected_text("Load")
+                        .show_ui(ui, |ui| {
+                            for (level, path) in LEVEL_FILES {
+                                if ui.selectable_label(false, format!("{:?}", level)).clicked() {
+                                    load = Some((res.assets.levels[&level].clone(), path));
+                                }
+                            }
+                        });
+                    if let Some((definition, path)) = load {
+                        *self = Editor::new(definition, path.to_owned());
+                        res.audio.bgm.play(self.definition.bgm);
+                    }
+                    ui.text_edit_singleline(&mut self.path);
+                });
+
+                ui.separator();
+
+                let definition = &mut self.definition;
+                let bgm = definition.bgm;
+                ComboBox::from_label("Music")
+                    .selected_text(format!("{:?}", definition.bgm))
+                    .show_ui(ui, |ui| {
+                        for track in bgm::TRACKS {
+                            ui.selectable_value(&mut definition.bgm, track, format!("{:?}", track));
+                        }
+                    });
+                if definition.bgm != bgm {
+                    res.audio.bgm.play(definition.bgm);
+                }
+                ui.horizontal(|ui| {
+                    ui.add(DragValue::new(&mut definition.max_score).speed(100.));
+                    ui.label("Max Score");
+                });
+
+                ui.label("Objective:");
+                ui.add(TextEdit::multiline(&mut self.objective_source).desired_rows(3));
+                if ui.button("Apply Objective").clicked() {
+                    match ron::from_str(&self.objective_source) {
+                        Ok(objective) => {
+                            definition.objective = objective;
+                            self.message = None;
+                            is_changed = true;
+                        }
+                        Err(err) => self.message = Some(format!("Invalid objective: {}", err)),
+                    }
+                }
+
+                ui.separator();
+
+                ComboBox::from_label("Place")
+                    .selected_text(tool_name(self.tool))
+                    .show_ui(ui, |ui| {
+                        let tools = (Building::VARIANTS.iter().map(|&v| Tool::Building(v)))
+                            .chain(Animal::VARIANTS.iter().map(|&v| Tool::Animal(v)))
+                            .chain(Enemy::VARIANTS.iter().map(|&v| Tool::Enemy(v)))
+                            .chain(Prop::VARIANTS.iter().map(|&v| Tool::Prop(v)))
+                            .chain([Tool::TextBubble]);
+                        for tool in tools {
+                            ui.selectable_value(&mut self.tool, tool, tool_name(tool));
+                        }
+                    });
+                ui.checkbox(&mut self.snap_to_grid, "Snap to Grid");
+                ui.checkbox(&mut self.draw_colliders, "Draw Colliders");
+
+                if let Some(item) = self.selected {
+                    ui.separator();
+                    ui.label(format!("Selected: {}", item_name(definition, item)));
+                    if let Item::TextBubble(i) = item {
+                        let mut text = definition.text_bubbles[i].rows.join("\n");
+                        if ui.text_edit_multiline(&mut text).changed() {
+                            let rows = text.lines().map(str::to_owned).collect();
+                            definition.text_bubbles[i].rows = rows;
+                            is_changed = true;
+                        }
+                    }
+                    if ui.button("Delete").clicked() {
+                        remove_item(definition, item);
+                        self.selected = None;
+                        is_changed = true;
+                    }
+                }
+
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    if ui.button("Play-test").clicked() {
+                        scene_change =
+                            SceneChange::Push(scenes::Combat::playtest(res, &self.definition));
+                    }
+                    if !cfg!(target_arch = "wasm32") && ui.button("Save").clicked() {
+                        self.save_with_message();
+                    }
+                });
+                if let Some(message) = &self.message {
+                    ui.label(message);
+                }
+            });
+
+        if self.is_confirming_leave {
+            Window::new("Unsaved Changes")
+                .resizable(false)
+                .collapsible(false)
+                .anchor(egui::Align2::CENTER_CENTER, (0., 0.))
+                .show(ctx, |ui| {
+                    ui.label("Leave the editor without saving?");
+                    ui.horizontal(|ui| {
+                        if !cfg!(target_arch = "wasm32") && ui.button("Save and Leave").clicked() {
+                            self.is_confirming_leave = false;
+                            if self.save_with_message() {
+                                scene_change = SceneChange::Change(scenes::MainMenu::boxed());
+                            }
+                        }
+                        if ui.button("Leave Without Saving").clicked() {
+                            scene_change = SceneChange::Change(scenes::MainMenu::boxed());
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.is_confirming_leave = false;
+                        }
+                    });
+                });
+        }
+
+        if is_changed {
+            self.rebuild();
+            self.has_unsaved_changes = true;
+        }
+        self.is_ui_focused = ctx.is_pointer_over_area() || ctx.wants_keyboard_input();
+        scene_change
+    }
+
+    fn draw(&self, res: &Resources) {
+        self.camera.enable();
+
+        self.background.draw(res);
+        for text_bubble in &self.text_bubbles {
+            text_bubble.draw(res);
+        }
+
+        let sim = &self.preview;
+        sim.death_ball.draw(res, &sim.world);
+        for animal in &sim.animals {
+            animal.draw(res, &sim.world);
+        }
+        for enemy in &sim.enemies {
+            enemy.draw(res, &sim.world);
+        }
+        for building in &sim.buildings {
+            building.draw(res, &sim.world);
+        }
+
+        if self.draw_colliders {
+            sim.world.physics.draw_colliders();
+        }
+
+        if let Some(item) = self.selected {
+            let tile_size = res.assets.props.cell_size;
+            let (x, y) = position_of(&self.definition, item, tile_size).into();
+            draw_circle_lines(x, y, PICK_RADIUS, SELECTED_THICKNESS, SELECTED_COLOR);
+        }
+
+        self.camera.disable();
+    }
+}
+
+fn objective_to_ron(objective: &Objective) -> String {
+    let config = ron::ser::PrettyConfig::new().indentor("  ".to_owned());
+    ron::ser::to_string_pretty(objective, config).unwrap()
+}
+
+fn tool_name(tool: Tool) -> String {
+    match tool {
+        Tool::Building(variant) => format!("Building: {:?}", variant),
+        Tool::Animal(variant) => format!("Animal: {:?}", variant),
+        Tool::Enemy(variant) => format!("Enemy: {:?}", variant),
+        Tool::Prop(prop) => format!("Prop: {:?}", prop),
+        Tool::TextBubble => "Text Bubble".to_owned(),
+    }
+}
+
+fn item_name(definition: &LevelDefinition, item: Item) -> String {
+    match item {
+        Item::Building(i) => format!("{:?}", definition.buildings[i].0),
+        Item::Animal(i) => format!("{:?}", definition.animals[i].0),
+        Item::Enemy(i) => format!("{:?}", definition.enemies[i].0),
+        Item::Prop(i) => format!("{:?}", definition.background.props[i].1),
+        Item::TextBubble(_) => "Text Bubble".to_owned(),
+    }
+}
+
+/// The background tile under the point, if it isn't before the background's offset
+fn to_tile(definition: &LevelDefinition, point: Vec2, tile_size: f32) -> Option<(u32, u32)> {
+    let tile = ((point - Vec2::from(definition.background.offset)) / tile_size).floor();
+    if tile.x < 0. || tile.y < 0. {
+        return None;
+    }
+    Some((tile.x as u32, tile.y as u32))
+}
+
+/// Where the item is grabbed from, for props that's the middle of their (top left) tile and for
+/// text bubbles their top left corner
+fn position_of(definition: &LevelDefinition, item: Item, tile_size: f32) -> Vec2 {
+    match item {
+        Item::Building(i) => definition.buildings[i].1.into(),
+        Item::Animal(i) => definition.animals[i].1.into(),
+        Item::Enemy(i) => definition.enemies[i].1.into(),
+        Item::Prop(i) => {
+            let (x, y) = definition.background.props[i].0;
+            let offset = Vec2::from(definition.background.offset);
+            vec2(x as f32 + 0.5, y as f32 + 0.5) * tile_size + offset
+        }
+        Item::TextBubble(i) => definition.text_bubbles[i].position.into(),
+    }
+}
+
+fn items(definition: &LevelDefinition) -> impl Iterator<Item = Item> {
+    (0..definition.buildings.len())
+        .map(Item::Building)
+        .chain((0..definition.animals.len()).map(Item::Animal))
+        .chain((0..definition.enemies.len()).map(Item::Enemy))
+        .chain((0..definition.background.props.len()).map(Item::Prop))
+        .chain((0..definition.text_bubbles.len()).map(Item::TextBubble))
+}
+
+/// The closest item within `PICK_RADIUS` of the point
+fn pick(definition: &LevelDefinition, point: Vec2, tile_size: f32) -> Option<Item> {
+    items(definition)
+        .map(|item| {
+            let distance = position_of(definition, item, tile_size).distance(point);
+            (item, distance)
+        })
+        .filter(|&(_, distance)| distance <= PICK_RADIUS)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(item, _)| item)
+}
+
+/// Returns whether or not the item moved
+fn move_item(definition: &mut LevelDefinition, item: Item, point: Vec2, tile_size: f32) -> bool {
+    if position_of(definition, item, tile_size) == point {
+        return false;
+    }
+    let position = point.into();
+    match item {
+        Item::Building(i) => definition.buildings[i].1 = position,
+        Item::Animal(i) => definition.animals[i].1 = position,
+        Item::Enemy(i) => definition.enemies[i].1 = position,
+        Item::Prop(i) => match to_tile(definition, point, tile_size) {
+            Some(tile) if tile != definition.background.props[i].0 => {
+                definition.background.props[i].0 = tile;
+            }
+            _ => return false,
+        },
+        Item::TextBubble(i) => definition.text_bubbles[i].position = position,
+    }
+    true
+}
+
+fn remove_item(definition: &mut LevelDefinition, item: Item) {
+    match item {
+        Item::Building(i) => {
+            definition.buildings.remove(i);
+        }
+        Item::Animal(i) => {
+            definition.animals.remove(i);
+        }
+        Item::Enemy(i) => {
+            definition.enemies.remove(i);
+        }
+        Item::Prop(i) => {
+            definition.background.props.remove(i);
+        }
+        Item::TextBubble(i) => {
+            definition.text_bubbles.remove(i);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TILE_SIZE: f32 = 32.;
+
+    fn level() -> LevelDefinition {
+        let mut definition = LevelDefinition::for_test(
+            "kind: None",
+            "animals: [(Horse, (0., 0.))], buildings: [(Barn, (100., 0.))]",
+        );
+        definition.background.offset = (-320., -320.);
+        definition.background.props.push(((10, 10), Prop::Hay));
+        definition
+    }
+
+    #[test]
+    fn should_pick_the_closest_item() {
+        let definition = level();
+        assert_eq!(
+            pick(&definition, vec2(20., 20.), TILE_SIZE),
+            Some(Item::Prop(0))
+        );
+        assert_eq!(
+            pick(&definition, vec2(-10., 0.), TILE_SIZE),
+            Some(Item::Animal(0))
+        );
+        assert_eq!(
+            pick(&definition, vec2(90., 0.), TILE_SIZE),
+            Some(Item::Building(0))
+        );
+        assert_eq!(pick(&definition, vec2(500., 0.), TILE_SIZE), None);
+    }
+
+    #[test]
+    fn should_move_props_a_tile_at_a_time() {
+        let mut definition = level();
+        assert!(!move_item(
+            &mut definition,
+            Item::Prop(0),
+            vec2(20., 20.),
+            TILE_SIZE
+        ));
+        assert!(move_item(
+            &mut definition,
+            Item::Prop(0),
+            vec2(40., 20.),
+            TILE_SIZE
+        ));
+        assert_eq!(definition.background.props[0].0, (11, 10));
+
+        // can't go before the offset, as tiles are unsigned
+        assert!(!move_item(
+            &mut definition,
+            Item::Prop(0),
+            vec2(-400., 0.),
+            TILE_SIZE
+        ));
+        assert_eq!(definition.background.props[0].0, (11, 10));
+    }
+
+    #[test]
+    fn should_round_trip_edits_through_ron() {
+        let mut definition = level();
+        move_item(
+            &mut definition,
+            Item::Building(0),
+            vec2(200., 50.),
+            TILE_SIZE,
+        );
+        remove_item(&mut definition, Item::Animal(0));
+
+        let definition = LevelDefinition::parse(&definition.to_ron()).unwrap();
+        assert_eq!(definition.buildings[0].1, (200., 50.));
+        assert!(definition.animals.is_empty());
+    }
+}
@@ -1,6 +1,14 @@
 use macroquad::prelude::*;
 
-use crate::{audio::bgm, levels, scenes, spritesheet::Sprite, Resources};
+use crate::{
+    animals::Animal,
+    audio::bgm,
+    levels::{self, Level},
+    rng::Rng,
+    scenes,
+    spritesheet::Sprite,
+    Resources,
+};
 
 use super::{Scene, SceneChange};
 
@@ -47,7 +55,7 @@ impl LevelSelect {
 
 impl Scene for LevelSelect {
     fn on_enter(&mut self, res: &mut Resources) {
-        if res.beaten.contains(&levels::Final) {
+        if res.save.beaten.contains(&levels::Final) {
             res.audio.bgm.play(bgm::TakeMeHome);
         } else {
             res.audio.bgm.play(bgm::Space);
@@ -71,7 +79,7 @@ impl Scene for LevelSelect {
                     self.dots + 1
                 }
                 _ => {
-                    let msg = &CHATTER_MESSAGES[rand::gen_range(0, CHATTER_MESSAGES.len())];
+                    let msg = &CHATTER_MESSAGES[res.rng.gen_range(0, CHATTER_MESSAGES.len())];
                     let len = self.chatter.len();
                     self.chatter[len - 1] = msg;
                     0
@@ -90,7 +98,7 @@ impl Scene for LevelSelect {
             .resizable(false)
             .anchor(egui::Align2::LEFT_TOP, (8., 8.))
             .show(ctx, |ui| {
-                ui.label(format!("Current Score: {}", res.score as f32 / 100.));
+                ui.label(format!("Current Score: {}", res.save.score as f32 / 100.));
             });
 
         let mut scene_change = SceneChange::None;
@@ -125,40 +133,44 @@ impl Scene for LevelSelect {
                             ui.with_layout(Layout::top_down_justified(Align::Center), |ui| {
                                 ui.spacing_mut().button_padding = vec2(0., 32.);
 
-                                let beat_scenario_1 = res.beaten.contains(&levels::Scenario1);
-                                let beat_scenario_2 = res.beaten.contains(&levels::Scenario2);
-                                let beat_final = res.beaten.contains(&levels::Final);
+                                let beat_scenario_1 = res.save.beaten.contains(&levels::Scenario1);
+                                let beat_scenario_2 = res.save.beaten.contains(&levels::Scenario2);
+                                let beat_final = res.save.beaten.contains(&levels::Final);
 
-                                if !beat_scenario_1 && ui.button("attack on humans").clicked() {
-                                    scene_change = SceneChange::Change(scenes::Combat::boxed(
-                                        res,
-                                        levels::Scenario1,
-                                    ));
+                                let mut level_to_load = None;
+                                if level_button(ui, res, "attack on humans", levels::Scenario1) {
+                                    level_to_load = Some(levels::Scenario1);
                                 }
-                                if !beat_scenario_2
-                                    && ui.button("down with the foundations").clicked()
-                                {
-                                    scene_change = SceneChange::Change(scenes::Combat::boxed(
-                                        res,
-                                        levels::Scenario2,
-                                    ));
+                                if level_button(
+                                    ui,
+                                    res,
+                                    "down with the foundations",
+                                    levels::Scenario2,
+                                ) {
+                                    level_to_load = Some(levels::Scenario2);
                                 }
                                 if beat_scenario_1 && beat_scenario_2 {
-                                    if !beat_final && ui.button("final level").clicked() {
-                                        scene_change = SceneChange::Change(scenes::Combat::boxed(
-                                            res,
-                                            levels::Final,
-                                        ));
+                                    if level_button(ui, res, "final level", levels::Final) {
+                                        level_to_load = Some(levels::Final);
                                     }
                                     if beat_final {
                                         let label = format!(
                                             "Thanks for playing! You finished with a score of {}",
-                                            res.score as f32 / 100.
+                                            res.save.score as f32 / 100.
                                         );
                                         let label = Label::new(label).wrap(false);
                                         ui.add(label);
+
+                                        if level_button(ui, res, "endless", levels::Endless) {
+                                            level_to_load = Some(levels::Endless);
+                                        }
                                     }
                                 }
+
+                                if let Some(level) = level_to_load {
+                                    scene_change =
+                                        SceneChange::Change(scenes::Combat::boxed(res, level));
+                                }
                             })
                         });
                     });
@@ -173,6 +185,45 @@ impl Scene for LevelSelect {
     }
 }
 
+/// Shows the button to start the level, next to the best results it's been beaten with
+fn level_button(ui: &mut egui::Ui, res: &Resources, text: &str, level: Level) -> bool {
+    ui.columns(2, |cols| {
+        let clicked = cols[0].button(text).clicked();
+
+        let ui = &mut cols[1];
+        match res.save.records.get(&level) {
+            Some(record) => {
+                ui.label(format!("{}/3 Stars", record.stars));
+                ui.label(format!("Best Score: {:.2}", record.score as f32 / 100.));
+                let minutes = (record.time / 60.).floor();
+                let seconds = record.time % 60.;
+                ui.label(format!("Best Time: {}:{:05.2}", minutes, seconds));
+                ui.label(format!(
+                    "Biggest Deathball: {}",
+                    record.peak_death_ball_size
+                ));
+                let found: Vec<_> = record.found.iter().map(|v| format!("{:?}", v)).collect();
+                ui.label(format!(
+                    "Animals Found: {}/{}",
+                    found.len(),
+                    Animal::VARIANTS.len()
+                ))
+                .on_hover_text(found.join(", "));
+            }
+            None => match res.save.most_waves_survived.get(&level) {
+                Some(waves) => {
+                    ui.label(format!("Most Waves Survived: {}", waves));
+                }
+                None => {
+                    ui.label("Not beaten yet");
+                }
+            },
+        }
+
+        clicked
+    })
+}
+
 struct Wanderer {
     sprite: Sprite,
     position: Vec2,
@@ -187,26 +238,26 @@ impl Wanderer {
         Wanderer {
             sprite,
             position: WANDER_INITIAL_POSITION.into(),
-            timer: Wanderer::random_timer(),
-            speed: Wanderer::random_speed(),
-            direction: Wanderer::random_direction(),
+            timer: Wanderer::random_timer(&mut res.rng),
+            speed: Wanderer::random_speed(&mut res.rng),
+            direction: Wanderer::random_direction(&mut res.rng),
         }
     }
 
-    fn random_timer() -> f32 {
-        rand::gen_range(WANDER_TIME.0, WANDER_TIME.1)
+    fn random_timer(rng: &mut Rng) -> f32 {
+        rng.gen_range(WANDER_TIME.0, WANDER_TIME.1)
     }
 
-    fn random_speed() -> f32 {
-        rand::gen_range(WANDER_SPEED.0, WANDER_SPEED.1)
+    fn random_speed(rng: &mut Rng) -> f32 {
+        rng.gen_range(WANDER_SPEED.0, WANDER_SPEED.1)
     }
 
-    fn random_direction() -> f32 {
+    fn random_direction(rng: &mut Rng) -> f32 {
         use std::f32::consts::PI;
-        rand::gen_range(-PI / 3., PI / 3.)
+        rng.gen_range(-PI / 3., PI / 3.)
     }
 
-    fn update(&mut self, res: &Resources) {
+    fn update(&mut self, res: &mut Resources) {
         let direction = vec2(self.direction.cos(), self.direction.sin());
         self.position += direction * self.speed * res.delta;
 
@@ -215,8 +266,8 @@ impl Wanderer {
             Rect::new(s, s, screen_width() - s - s, screen_height() - s - s)
         };
         if !region.contains(self.position) {
-            self.timer = Wanderer::random_timer();
-            self.speed = Wanderer::random_speed();
+            self.timer = Wanderer::random_timer(&mut res.rng);
+            self.speed = Wanderer::random_speed(&mut res.rng);
             self.direction += std::f32::consts::PI;
 
             self.position.x = self.position.x.clamp(region.x, region.x + region.w);
@@ -225,9 +276,9 @@ impl Wanderer {
 
         self.timer -= res.delta;
         if self.timer < 0. {
-            self.timer = Wanderer::random_timer();
-            self.speed = Wanderer::random_speed();
-            self.direction += Wanderer::random_direction();
+            self.timer = Wanderer::random_timer(&mut res.rng);
+            self.speed = Wanderer::random_speed(&mut res.rng);
+            self.direction += Wanderer::random_direction(&mut res.rng);
         }
     }
 
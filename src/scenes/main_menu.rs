@@ -57,12 +57,24 @@ impl Scene for MainMenu {
                             ui.with_layout(Layout::top_down_justified(Align::Center), |ui| {
                                 ui.spacing_mut().button_padding = vec2(0., 32.);
 
+                                if res.save.has_progress() && ui.button("Continue").clicked() {
+                                    scene_change =
+                                        SceneChange::Change(scenes::LevelSelect::boxed(res));
+                                }
                                 if ui.button("New Game").clicked() {
+                                    res.save.reset_progress();
+                                    res.save.store();
                                     scene_change = SceneChange::Change(scenes::Combat::boxed(
                                         res,
                                         levels::Tutorial,
                                     ));
                                 }
+                                if ui.button("Settings").clicked() {
+                                    scene_change = SceneChange::Push(scenes::Settings::boxed());
+                                }
+                                if res.debug.enable_editor && ui.button("Level Editor").clicked() {
+                                    scene_change = SceneChange::Change(scenes::Editor::boxed());
+                                }
                                 if !cfg!(target_arch = "wasm32") && ui.button("Quit").clicked() {
                                     scene_change = SceneChange::Quit;
                                 }
@@ -71,7 +83,7 @@ impl Scene for MainMenu {
                     });
             });
 
-        if crate::debug::ENABLE_LEVEL_SELECT {
+        if res.debug.enable_level_select {
             let mut level_to_load = None;
             Window::new("Load Level")
                 .resizable(false)
@@ -88,6 +100,8 @@ impl Scene for MainMenu {
                         level_to_load = Some(levels::Scenario2);
                     } else if ui.button("Final Scenario").clicked() {
                         level_to_load = Some(levels::Final);
+                    } else if ui.button("Endless").clicked() {
+                        level_to_load = Some(levels::Endless);
                     }
                 });
             scene_change = level_to_load.map_or(SceneChange::None, |level| {
@@ -1,12 +1,10 @@
-use crate::{physics::PhysicsEvent, Resources};
+use crate::Resources;
 
 pub trait Scene {
     fn on_enter(&mut self, _res: &mut Resources) {}
 
     fn update(&mut self, res: &mut Resources) -> SceneChange;
 
-    fn handle_physics_event(&mut self, _res: &mut Resources, _event: PhysicsEvent) {}
-
     fn update_ui(&mut self, res: &mut Resources, ctx: &egui::CtxRef) -> SceneChange;
 
     fn draw(&self, res: &Resources);
@@ -14,7 +12,12 @@ pub trait Scene {
 
 pub enum SceneChange {
     None,
+    /// Replaces every scene, including any underneath the current one
     Change(Box<dyn Scene>),
+    /// Shows a scene on top of the current one, which is kept drawn but no longer updated
+    Push(Box<dyn Scene>),
+    /// Returns to the scene underneath, without entering it again
+    Pop,
     Quit,
 }
 
@@ -26,5 +29,14 @@ pub use main_menu::MainMenu;
 pub mod combat;
 pub use combat::Combat;
 
+mod editor;
+pub use editor::Editor;
+
 pub mod level_select;
 pub use level_select::LevelSelect;
+
+mod pause;
+pub use pause::Pause;
+
+mod settings;
+pub use settings::Settings;
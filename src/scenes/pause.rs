@@ -0,0 +1,82 @@
+use macroquad::prelude::*;
+
+use crate::{levels::Level, scenes, Resources};
+
+use super::{Scene, SceneChange};
+
+const OVERLAY_COLOR: Color = Color::new(0., 0., 0., 0.5);
+
+/// Shown on top of `Combat`, which is frozen for as long as this is the current scene
+pub struct Pause {
+    level: Level,
+    /// For levels with waves, recorded if the run is left from here
+    waves_survived: Option<u16>,
+}
+
+impl Pause {
+    pub fn boxed(level: Level, waves_survived: Option<u16>) -> Box<Self> {
+        Box::new(Pause {
+            level,
+            waves_survived,
+        })
+    }
+
+    fn end_run(&self, res: &mut Resources) {
+        if let Some(waves) = self.waves_survived {
+            res.save.record_waves_survived(self.level, waves);
+            res.save.store();
+        }
+    }
+}
+
+impl Scene for Pause {
+    fn update(&mut self, res: &mut Resources) -> SceneChange {
+        if res.input.pause() {
+            return SceneChange::Pop;
+        }
+        SceneChange::None
+    }
+
+    fn update_ui(&mut self, res: &mut Resources, ctx: &egui::CtxRef) -> SceneChange {
+        use egui::*;
+
+        let mut scene_change = SceneChange::None;
+
+        Area::new("Pause")
+            .movable(false)
+            .anchor(egui::Align2::CENTER_CENTER, (0., 0.))
+            .show(ctx, |ui| {
+                Frame::window(&ctx.style()).show(ui, |ui| {
+                    ui.with_layout(Layout::top_down_justified(Align::Center), |ui| {
+                        ui.add(Label::new(RichText::new("Paused").heading()).wrap(false));
+
+                        ui.allocate_exact_size(vec2(0., 16.), Sense::hover());
+
+                        ui.spacing_mut().button_padding = vec2(0., 16.);
+
+                        if ui.button("Resume").clicked() {
+                            scene_change = SceneChange::Pop;
+                        }
+                        if ui.button("Restart Level").clicked() {
+                            self.end_run(res);
+                            scene_change =
+                                SceneChange::Change(scenes::Combat::boxed(res, self.level));
+                        }
+                        if ui.button("Return to Level Select").clicked() {
+                            self.end_run(res);
+                            scene_change = SceneChange::Change(scenes::LevelSelect::boxed(res));
+                        }
+                        if ui.button("Settings").clicked() {
+                            scene_change = SceneChange::Push(scenes::Settings::boxed());
+                        }
+                    })
+                });
+            });
+
+        scene_change
+    }
+
+    fn draw(&self, _res: &Resources) {
+        draw_rectangle(0., 0., screen_width(), screen_height(), OVERLAY_COLOR);
+    }
+}
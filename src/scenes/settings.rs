@@ -0,0 +1,146 @@
+use crate::{
+    camera::FOLLOW_MODES,
+    input::{
+        bindings::{Action, Bindings},
+        gamepad,
+    },
+    Resources,
+};
+
+use super::{Scene, SceneChange};
+
+/// Whether each action can be rebound, as only those bound to single buttons can be
+const CONTROLS: [(&str, Action, bool); 8] = [
+    ("Move Deathball", Action::MoveDeathball, true),
+    ("Pan Camera", Action::PanCamera, false),
+    ("Zoom Camera", Action::Zoom, false),
+    ("Reset Camera", Action::ResetCamera, true),
+    ("Confirm", Action::Confirm, true),
+    ("Pause", Action::Pause, true),
+    ("Fast Forward", Action::FastForward, true),
+    ("Save Replay", Action::SaveReplay, true),
+];
+
+/// Shown on top of another scene, and changes are applied as soon as they're made
+pub struct Settings {
+    /// Waiting for the next button press to bind to the action
+    rebinding: Option<Action>,
+}
+
+impl Settings {
+    pub fn boxed() -> Box<Self> {
+        Box::new(Settings { rebinding: None })
+    }
+}
+
+impl Scene for Settings {
+    fn update(&mut self, res: &mut Resources) -> SceneChange {
+        if let Some(action) = self.rebinding {
+            if let Some(binding) = res.input.last_pressed() {
+                res.save.settings.bindings.rebind(action, binding);
+                res.input.apply_settings(&res.save.settings);
+                self.rebinding = None;
+            }
+            return SceneChange::None;
+        }
+
+        if res.input.pause() {
+            res.save.store();
+            return SceneChange::Pop;
+        }
+        SceneChange::None
+    }
+
+    fn update_ui(&mut self, res: &mut Resources, ctx: &egui::CtxRef) -> SceneChange {
+        use egui::*;
+
+        let mut scene_change = SceneChange::None;
+
+        Area::new("Settings")
+            .movable(false)
+            .anchor(egui::Align2::CENTER_CENTER, (0., 0.))
+            .show(ctx, |ui| {
+                Frame::window(&ctx.style()).show(ui, |ui| {
+                    ui.with_layout(Layout::top_down_justified(Align::Center), |ui| {
+                        ui.add(Label::new(RichText::new("Settings").heading()).wrap(false));
+
+                        ui.allocate_exact_size(vec2(0., 16.), Sense::hover());
+
+                        let settings = &mut res.save.settings;
+
+                        let music = Slider::new(&mut settings.bgm_volume, 0.0..=1.0).text("Music");
+                        let sfx =
+                            Slider::new(&mut settings.sfx_volume, 0.0..=1.0).text("Sound Effects");
+                        if ui.add(music).changed() | ui.add(sfx).changed() {
+                            res.audio.apply_settings(settings);
+                        }
+
+                        let pan_speed =
+                            Slider::new(&mut settings.pan_speed, 5.0..=40.0).text("Camera Speed");
+                        let zoom_sensitivity =
+                            Slider::new(&mut settings.zoom_sensitivity, 0.25..=3.0)
+                                .text("Zoom Sensitivity");
+                        if ui.add(pan_speed).changed() | ui.add(zoom_sensitivity).changed() {
+                            res.input.apply_settings(settings);
+                        }
+
+                        if ui
+                            .checkbox(&mut settings.fullscreen, "Fullscreen")
+                            .changed()
+                        {
+                            crate::set_fullscreen(settings.fullscreen);
+                        }
+
+                        ComboBox::from_label("Camera Follow")
+                            .selected_text(format!("{:?}", settings.camera_follow))
+                            .show_ui(ui, |ui| {
+                                for follow in FOLLOW_MODES {
+                                    let name = format!("{:?}", follow);
+                                    ui.selectable_value(&mut settings.camera_follow, follow, name);
+                                }
+                            });
+                        ui.checkbox(&mut settings.screen_shake, "Screen Shake");
+
+                        ui.allocate_exact_size(vec2(0., 16.), Sense::hover());
+
+                        ui.label("Controls");
+                        Grid::new("controls").striped(true).show(ui, |ui| {
+                            for (name, action, can_rebind) in CONTROLS {
+                                if action == Action::SaveReplay && cfg!(target_arch = "wasm32") {
+                                    continue;
+                                }
+                                ui.label(name);
+                                let bindings = (settings.bindings.get(action).iter())
+                                    .filter(|b| gamepad::IS_SUPPORTED || !b.is_gamepad());
+                                let bindings: Vec<_> = bindings.map(|b| b.to_string()).collect();
+                                ui.label(bindings.join(", "));
+                                if self.rebinding == Some(action) {
+                                    ui.label("Press any button...");
+                                } else if can_rebind && ui.button("Rebind").clicked() {
+                                    self.rebinding = Some(action);
+                                }
+                                ui.end_row();
+                            }
+                        });
+                        if ui.button("Reset Controls").clicked() {
+                            settings.bindings = Bindings::default();
+                            res.input.apply_settings(settings);
+                        }
+
+                        ui.allocate_exact_size(vec2(0., 16.), Sense::hover());
+
+                        ui.spacing_mut().button_padding = vec2(0., 16.);
+
+                        if ui.button("Back").clicked() {
+                            res.save.store();
+                            scene_change = SceneChange::Pop;
+                        }
+                    })
+                });
+            });
+
+        scene_change
+    }
+
+    fn draw(&self, _res: &Resources) {}
+}
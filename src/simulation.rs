@@ -0,0 +1,1149 @@
+//! The gameplay half of a combat level with no rendering or audio, so that it can be run headless.
+//!
+//! The simulation only ever advances in fixed steps of `DT`, driven by `Commands`, so the same
+//! level given the same commands plays out the same way whether or not anything is watching.
+//! Observers (ie. the `Combat` scene) draw the entities and react to the emitted `Event`s.
+
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    animals::{self, Ability, Animal},
+    buildings::{self, Building},
+    death_ball::DeathBall,
+    debris::Debris,
+    drops::Drops,
+    enemies::{self, Enemy},
+    entities::{Entities, GenerationalIndex},
+    fire::Fire,
+    groups,
+    levels::{Level, LevelDefinition},
+    objectives::Objective,
+    physics::{self, Physics, PhysicsEvent, PhysicsEventKind},
+    projectiles::Projectile,
+    rng::Rng,
+    spawners::Spawner,
+};
+
+pub const DT: f32 = physics::DT;
+/// Per update, enough for fast forward at the highest debug time scale (12x) at 60 FPS
+pub const MAX_STEPS: u8 = 12;
+
+const LOSE_TIME: f32 = 5.;
+
+/// Between animals added to the death ball at once, so that they don't start out overlapping
+const DEATH_BALL_SPACING: f32 = 16.;
+/// For deriving the debris stream from the gameplay one
+const DEBRIS_SALT: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// The state that is shared between every entity in a simulation
+pub struct World {
+    pub physics: Physics,
+    pub deleted: Vec<GenerationalIndex>,
+    /// Entities to add once every entity has been updated
+    pub spawned: Vec<Spawn>,
+    pub delta: f32,
+    /// The gameplay stream, cosmetic effects must not draw from it
+    pub rng: Rng,
+    /// Its own stream, so that how buildings break apart doesn't change the gameplay stream
+    pub debris_rng: Rng,
+}
+
+impl World {
+    pub fn new(rng: Rng) -> Self {
+        World {
+            physics: Physics::new(),
+            deleted: Vec::new(),
+            spawned: Vec::new(),
+            delta: DT,
+            debris_rng: rng.fork(DEBRIS_SALT),
+            rng,
+        }
+    }
+}
+
+pub enum Spawn {
+    Animal(animals::Variant, Vec2),
+    /// Whatever the building's drop table rolls, if anything
+    Drop(buildings::Variant, Vec2),
+    Enemy(enemies::Variant, Vec2),
+    Projectile {
+        position: Vec2,
+        velocity: Vec2,
+        impulse: f32,
+        damage: u8,
+    },
+    Debris {
+        position: Vec2,
+        velocity: Vec2,
+        source: Rect,
+    },
+    /// Goes off straight away, rather than being an entity
+    Explosion {
+        position: Vec2,
+        radius: f32,
+        impulse: f32,
+        damage: u8,
+    },
+    Fire {
+        position: Vec2,
+        radius: f32,
+        damage: u8,
+        duration: f32,
+    },
+}
+
+/// What can be added to a level part way through, see `Simulation::spawn`
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Spawnable {
+    Animal(animals::Variant),
+    Enemy(enemies::Variant),
+    Building(buildings::Variant),
+}
+
+/// Changes to a level from the debug window, kept in replays so that they play back the same
+#[derive(Clone, Serialize, Deserialize)]
+pub enum Cheat {
+    Spawn(Spawnable, (f32, f32)),
+    KillEnemies,
+    SetDeathBallSize(u8),
+    CompleteObjective,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Status {
+    Playing,
+    Losing { timer: f32 },
+    HasLost,
+    HasWon,
+}
+
+/// Things that happened during a step that observers may want to react to. `Killed` is for
+/// enemies and buildings, killing a boss sends both it and `BossKilled`
+pub enum Event {
+    Hit { point: Vec2 },
+    Killed { point: Vec2 },
+    AnimalKilled { point: Vec2 },
+    BossKilled,
+    Exploded { point: Vec2, radius: f32 },
+}
+
+/// The player's input for a step, in world coordinates
+#[derive(Clone, Default)]
+pub struct Commands {
+    pub move_deathball: Option<Vec2>,
+}
+
+pub struct Simulation {
+    pub world: World,
+    pub level: Level,
+    pub objective: Objective,
+    pub death_ball: DeathBall,
+    pub animals: Entities<Animal, { groups::ANIMAL }>,
+    pub buildings: Entities<Building, { groups::BUILDING }>,
+    pub enemies: Entities<Enemy, { groups::ENEMY }>,
+    pub projectiles: Entities<Projectile, { groups::PROJECTILE }>,
+    pub debris: Entities<Debris, { groups::DEBRIS }>,
+    pub fires: Entities<Fire, { groups::FIRE }>,
+    pub drops: Drops,
+    pub spawners: Vec<Spawner>,
+    pub death_ball_size: u8,
+    pub peak_death_ball_size: u8,
+    pub max_score: u32,
+    pub score: f32,
+    /// How long the level has been played for, stops counting once won
+    pub time: f32,
+    pub status: Status,
+    events: Vec<Event>,
+    physics_events: Vec<PhysicsEvent>,
+    accumulator: f32,
+}
+
+impl Simulation {
+    pub fn new(level: Level, definition: &LevelDefinition, rng: Rng) -> Self {
+        let mut world = World::new(rng);
+
+        let death_ball = DeathBall::new(&mut world, Vec2::ZERO);
+
+        let mut animals = Entities::new();
+        for &(variant, position) in &definition.animals {
+            animals.push(|idx| Animal::new(variant, idx, &mut world, position.into()));
+        }
+
+        let mut buildings = Entities::new();
+        for &(variant, position) in &definition.buildings {
+            buildings.push(|idx| Building::new(variant, idx, &mut world, position.into()));
+        }
+        for (tag, variant, position) in &definition.tagged_buildings {
+            buildings.push(|idx| {
+                Building::new(*variant, idx, &mut world, (*position).into()).with_tag(tag.clone())
+            });
+        }
+
+        let mut enemies = Entities::new();
+        for &(variant, position) in &definition.enemies {
+            enemies.push(|idx| Enemy::new(variant, idx, &mut world, position.into()));
+        }
+
+        let spawners = definition
+            .spawners
+            .iter()
+            .map(|spawner| {
+                let position = spawner.position.into();
+                Spawner::new(position, spawner.waves.clone(), spawner.endless)
+            })
+            .collect();
+
+        Simulation {
+            world,
+            level,
+            objective: definition.objective.clone(),
+            death_ball,
+            animals,
+            buildings,
+            enemies,
+            projectiles: Entities::new(),
+            debris: Entities::new(),
+            fires: Entities::new(),
+            drops: Drops::new(definition.drops.clone()),
+            spawners,
+            death_ball_size: 0,
+            peak_death_ball_size: 0,
+            max_score: definition.max_score,
+            score: definition.max_score as f32,
+            time: 0.,
+            status: Status::Playing,
+            events: Vec::new(),
+            physics_events: Vec::new(),
+            accumulator: 0.,
+        }
+    }
+
+    /// The number of waves every spawner has made it through, or 0 if there are none
+    pub fn waves_survived(&self) -> u16 {
+        let survived = self.spawners.iter().map(Spawner::waves_survived).min();
+        survived.unwrap_or(0)
+    }
+
+    /// Takes as many steps as fit into the given (real) time, returns the number of steps taken
+    pub fn update(&mut self, delta: f32, commands: &Commands) -> u8 {
+        // source: https://gafferongames.com/post/fix_your_timestep
+        self.accumulator += delta;
+        let mut steps_taken = 0;
+        while self.accumulator >= DT && steps_taken < MAX_STEPS {
+            steps_taken += 1;
+            self.step(commands);
+            self.accumulator -= DT;
+        }
+        steps_taken
+    }
+
+    pub fn step(&mut self, commands: &Commands) {
+        // Update entities
+        self.death_ball
+            .update(&mut self.world, commands.move_deathball);
+        for animal in &mut self.animals {
+            animal.update(&mut self.world, &self.death_ball);
+        }
+        for building in &mut self.buildings {
+            building.update(&mut self.world);
+        }
+        for spawner in &mut self.spawners {
+            spawner.update(&mut self.world);
+        }
+        for enemy in &mut self.enemies {
+            let just_killed = enemy.update(&mut self.world, &self.animals, &self.death_ball);
+            if just_killed {
+                let point = enemy.get_position(&self.world);
+                kill_enemy(
+                    &mut self.objective,
+                    &mut self.events,
+                    point,
+                    enemy.is_boss(),
+                );
+            }
+        }
+        for projectile in &mut self.projectiles {
+            projectile.update(&mut self.world);
+        }
+        for debris in &mut self.debris {
+            debris.update(&mut self.world);
+        }
+        let mut burning = Vec::new();
+        for fire in &mut self.fires {
+            if fire.update(&mut self.world) {
+                burning.push((fire.position, fire.radius, fire.damage));
+            }
+        }
+        for (position, radius, damage) in burning {
+            self.burn(position, radius, damage);
+        }
+
+        // Add spawned entities
+        for spawn in std::mem::take(&mut self.world.spawned) {
+            match spawn {
+                Spawn::Animal(variant, position) => {
+                    self.drops.add_found(variant);
+                    let world = &mut self.world;
+                    self.animals
+                        .push(|idx| Animal::new(variant, idx, world, position));
+                }
+                Spawn::Drop(building, position) => {
+                    if let Some(variant) = self.drops.roll(building, &mut self.world.rng) {
+                        let world = &mut self.world;
+                        self.animals
+                            .push(|idx| Animal::new(variant, idx, world, position));
+                    }
+                }
+                Spawn::Enemy(variant, position) => {
+                    let world = &mut self.world;
+                    self.enemies
+                        .push(|idx| Enemy::new(variant, idx, world, position));
+                }
+                Spawn::Projectile {
+                    position,
+                    velocity,
+                    impulse,
+                    damage,
+                } => {
+                    let world = &mut self.world;
+                    self.projectiles.push(|idx| {
+                        Projectile::new(idx, world, position, velocity, impulse, damage)
+                    });
+                }
+                Spawn::Debris {
+                    position,
+                    velocity,
+                    source,
+                } => {
+                    let world = &mut self.world;
+                    self.debris
+                        .push(|idx| Debris::new(idx, world, position, velocity, source));
+                }
+                Spawn::Explosion {
+                    position,
+                    radius,
+                    impulse,
+                    damage,
+                } => self.explode(position, radius, impulse, damage),
+                Spawn::Fire {
+                    position,
+                    radius,
+                    damage,
+                    duration,
+                } => {
+                    self.fires
+                        .push(|idx| Fire::new(idx, position, radius, damage, duration));
+                }
+            }
+        }
+
+        // Clear deleted entities
+        for idx in self.world.deleted.drain(..) {
+            match idx.group() {
+                groups::ANIMAL => self.animals.remove(idx),
+                groups::BUILDING => self.buildings.remove(idx),
+                groups::ENEMY => self.enemies.remove(idx),
+                groups::PROJECTILE => self.projectiles.remove(idx),
+                groups::DEBRIS => self.debris.remove(idx),
+                groups::FIRE => self.fires.remove(idx),
+                _ => {}
+            };
+        }
+
+        // handle status changes
+        if let Status::Playing = self.status {
+            let position = self.death_ball.get_position(&self.world);
+            self.objective
+                .on_move_death_ball(position, self.death_ball_size);
+            self.objective.on_survive_waves(self.waves_survived());
+            self.objective.on_survive_time(self.time);
+        }
+        self.objective.update(self.time);
+        self.score += self.objective.take_bonus_score() as f32;
+        match self.status {
+            Status::Playing | Status::Losing { .. } if self.objective.is_complete() => {
+                self.status = Status::HasWon;
+            }
+            Status::Playing | Status::Losing { .. } if self.objective.is_failed() => {
+                self.status = Status::HasLost;
+            }
+            Status::Playing if self.death_ball_size == 0 => {
+                self.status = Status::Losing { timer: LOSE_TIME };
+            }
+            Status::Losing { .. } if self.death_ball_size > 0 => {
+                self.status = Status::Playing;
+            }
+            Status::Losing { ref mut timer } => {
+                *timer -= self.world.delta;
+                if *timer < 0. {
+                    self.status = Status::HasLost;
+                }
+            }
+            _ => {}
+        }
+
+        if !matches!(self.status, Status::HasWon) {
+            self.score = (self.score - self.world.delta * 100.).max(0.);
+            self.time += self.world.delta;
+        }
+
+        // Update physics
+        let mut physics_events = std::mem::take(&mut self.physics_events);
+        self.world.physics.step(&mut physics_events);
+        for event in physics_events.drain(..) {
+            self.handle_physics_event(event);
+        }
+        self.physics_events = physics_events;
+    }
+
+    /// Takes the events emitted since the last time this was called
+    pub fn drain_events(&mut self) -> impl Iterator<Item = Event> + '_ {
+        self.events.drain(..)
+    }
+
+    fn update_death_ball_size(&mut self) {
+        self.death_ball_size = self
+            .animals
+            .into_iter()
+            .filter(|a| a.is_affected_by_death_ball)
+            .count() as u8;
+        self.peak_death_ball_size = self.peak_death_ball_size.max(self.death_ball_size);
+
+        let variants: Vec<_> = self
+            .animals
+            .into_iter()
+            .filter(|a| a.is_affected_by_death_ball)
+            .map(|a| a.variant())
+            .collect();
+        self.objective.on_update_death_ball(&variants);
+    }
+
+    fn handle_physics_event(&mut self, event: PhysicsEvent) {
+        let idx1 = self.world.physics.get_idx(event.collider1);
+        let idx2 = self.world.physics.get_idx(event.collider2);
+
+        // dead animals are only left around to fade away
+        for idx in [idx1, idx2] {
+            if idx.group() == groups::ANIMAL && !self.animals[idx].is_alive() {
+                return;
+            }
+        }
+
+        // DeathBall with Animal
+        if idx1 == groups::DEATH_BALL && idx2.group() == groups::ANIMAL {
+            let animal = &mut self.animals[idx2];
+            animal.is_affected_by_death_ball = true;
+
+            self.update_death_ball_size();
+            return;
+        }
+
+        // Animal with Building
+        if idx1.group() == groups::ANIMAL && idx2.group() == groups::BUILDING {
+            let animal = &mut self.animals[idx1];
+            let building = &mut self.buildings[idx2];
+
+            if let PhysicsEventKind::ContactStart { point } = event.kind {
+                if let Some(range) = building.pull() {
+                    let lever = building.get_position();
+                    self.open_gates(lever, range);
+                    self.events.push(Event::Hit { point });
+                    return;
+                }
+
+                let just_destroyed = building.damage(animal.damage);
+                if just_destroyed {
+                    self.objective.on_destroy_building(building.tag());
+                    self.events.push(Event::Killed { point });
+                } else {
+                    self.events.push(Event::Hit { point });
+                }
+            }
+
+            return;
+        }
+
+        // Animal with Enemy
+        if idx1.group() == groups::ANIMAL && idx2.group() == groups::ENEMY {
+            let animal = &mut self.animals[idx1];
+            let enemy = &mut self.enemies[idx2];
+
+            match event.kind {
+                // will only happen for detection range sensor
+                PhysicsEventKind::IntersectStart => enemy.add_nearby(event.collider1),
+                PhysicsEventKind::IntersectEnd => enemy.remove_nearby(event.collider1),
+
+                // will only happen for collision body
+                PhysicsEventKind::ContactStart { point } => {
+                    let just_killed = enemy.damage(animal.damage);
+                    if just_killed {
+                        kill_enemy(
+                            &mut self.objective,
+                            &mut self.events,
+                            point,
+                            enemy.is_boss(),
+                        );
+                    } else {
+                        self.events.push(Event::Hit { point });
+
+                        if let Some(ability) = animal.ability() {
+                            let physics = &self.world.physics;
+                            let animal_pos = physics.get_position(event.collider1);
+                            let enemy_pos = physics.get_position(event.collider2);
+                            let direction = (enemy_pos - animal_pos).normalize_or_zero();
+                            enemy.afflict(ability, direction);
+                        }
+                    }
+                }
+                _ => {}
+            }
+
+            return;
+        }
+
+        // Animal with Enemy Attacks
+        if idx1.group() == groups::ANIMAL && idx2.group() == groups::ENEMY_ATTACK {
+            let animal_handle = event.collider1;
+            let enemy = &self.enemies[idx2.with_group(groups::ENEMY)];
+            let enemy_handle = event.collider2;
+
+            let physics = &self.world.physics;
+            let animal_pos = physics.get_position(animal_handle);
+            let enemy_pos = physics.get_position(enemy_handle);
+            let direction = (animal_pos - enemy_pos).normalize_or_zero();
+            let impulse = direction * enemy.attack_impulse;
+
+            // the knockback applies for as long as the attack overlaps, but damage only once
+            let damage = match event.kind {
+                PhysicsEventKind::IntersectStart => enemy.attack_damage,
+                _ => 0,
+            };
+            self.hit_animal(idx1, impulse, damage);
+            return;
+        }
+
+        // Animal with Projectile
+        if idx1.group() == groups::ANIMAL && idx2.group() == groups::PROJECTILE {
+            let projectile = &mut self.projectiles[idx2];
+            if let PhysicsEventKind::IntersectStart = event.kind {
+                if projectile.hit() {
+                    let impulse = projectile.direction * projectile.impulse;
+                    let damage = projectile.damage;
+                    let just_killed = self.hit_animal(idx1, impulse, damage);
+                    if !just_killed {
+                        let point = self.world.physics.get_position(event.collider1);
+                        self.events.push(Event::Hit { point });
+                    }
+                }
+            }
+            return;
+        }
+
+        // Building with Projectile
+        if idx1.group() == groups::BUILDING && idx2.group() == groups::PROJECTILE {
+            if let PhysicsEventKind::IntersectStart = event.kind {
+                self.projectiles[idx2].hit();
+            }
+        }
+    }
+
+    /// Knocks the animal back, maybe out of the death ball, and damages it, returns whether or
+    /// not the animal was killed
+    fn hit_animal(&mut self, animal_idx: GenerationalIndex, impulse: Vec2, damage: u8) -> bool {
+        self.animals[animal_idx].knock_back(&mut self.world, impulse);
+        self.update_death_ball_size();
+        self.hurt_animal(animal_idx, damage)
+    }
+
+    /// Returns whether or not the animal was killed
+    fn hurt_animal(&mut self, animal_idx: GenerationalIndex, damage: u8) -> bool {
+        let animal = &mut self.animals[animal_idx];
+        let just_killed = animal.hurt(damage);
+
+        if just_killed {
+            // removing its collider later won't tell the enemies that it's gone
+            let handle = animal.handle();
+            for enemy in &mut self.enemies {
+                enemy.remove_nearby(handle);
+            }
+            let point = animal.get_position(&self.world);
+            self.events.push(Event::AnimalKilled { point });
+
+            self.update_death_ball_size();
+        }
+        just_killed
+    }
+
+    /// Knocks back everything in range, the closer the harder, and damages enemies and buildings
+    fn explode(&mut self, position: Vec2, radius: f32, impulse: f32, damage: u8) {
+        self.events.push(Event::Exploded {
+            point: position,
+            radius,
+        });
+
+        let blast = |point: Vec2| {
+            let offset = point - position;
+            let distance = offset.length();
+            (distance <= radius)
+                .then(|| offset.normalize_or_zero() * impulse * (1. - distance / radius))
+        };
+
+        let world = &mut self.world;
+        for animal in &mut self.animals {
+            if let Some(impulse) = blast(animal.get_position(world)) {
+                if animal.is_alive() {
+                    animal.knock_back(world, impulse);
+                }
+            }
+        }
+        self.update_death_ball_size();
+
+        let world = &mut self.world;
+        for enemy in &mut self.enemies {
+            if let Some(impulse) = blast(enemy.get_position(world)) {
+                if enemy.damage(damage) {
+                    let point = enemy.get_position(world);
+                    kill_enemy(
+                        &mut self.objective,
+                        &mut self.events,
+                        point,
+                        enemy.is_boss(),
+                    );
+                } else {
+                    let knockback = Ability::Knockback {
+                        speed: impulse.length(),
+                    };
+                    enemy.afflict(knockback, impulse.normalize_or_zero());
+                }
+            }
+        }
+
+        // other explosive buildings go off on their next update, setting off chain reactions
+        for building in &mut self.buildings {
+            let point = building.get_position();
+            if blast(point).is_some() && building.damage(damage) {
+                self.objective.on_destroy_building(building.tag());
+                self.events.push(Event::Killed { point });
+            }
+        }
+
+        for debris in &mut self.debris {
+            if let Some(impulse) = blast(debris.get_position(world)) {
+                debris.push(world, impulse);
+            }
+        }
+    }
+
+    /// Damages every living animal and enemy in range
+    fn burn(&mut self, position: Vec2, radius: f32, damage: u8) {
+        let world = &self.world;
+        let is_in_range = |point: Vec2| point.distance(position) <= radius;
+
+        let burnt: Vec<_> = self
+            .animals
+            .into_iter()
+            .filter(|animal| animal.is_alive() && is_in_range(animal.get_position(world)))
+            .map(Animal::idx)
+            .collect();
+        for animal_idx in burnt {
+            self.hurt_animal(animal_idx, damage);
+        }
+
+        let world = &self.world;
+        for enemy in &mut self.enemies {
+            let point = enemy.get_position(world);
+            if is_in_range(point) && enemy.damage(damage) {
+                kill_enemy(
+                    &mut self.objective,
+                    &mut self.events,
+                    point,
+                    enemy.is_boss(),
+                );
+            }
+        }
+    }
+
+    fn open_gates(&mut self, lever: Vec2, range: f32) {
+        for building in &mut self.buildings {
+            if building.is_gate() && building.get_position().distance(lever) <= range {
+                building.open();
+            }
+        }
+    }
+
+    pub fn cheat(&mut self, cheat: &Cheat) {
+        match *cheat {
+            Cheat::Spawn(spawnable, position) => self.spawn(spawnable, position.into()),
+            Cheat::KillEnemies => self.kill_enemies(),
+            Cheat::SetDeathBallSize(size) => self.set_death_ball_size(size),
+            Cheat::CompleteObjective => self.objective.complete(),
+        }
+    }
+
+    /// Adds the entity straight away, without counting towards the animals found
+    fn spawn(&mut self, spawnable: Spawnable, position: Vec2) {
+        let world = &mut self.world;
+        match spawnable {
+            Spawnable::Animal(variant) => {
+                self.animals
+                    .push(|idx| Animal::new(variant, idx, world, position));
+            }
+            Spawnable::Enemy(variant) => {
+                self.enemies
+                    .push(|idx| Enemy::new(variant, idx, world, position));
+            }
+            Spawnable::Building(variant) => {
+                self.buildings
+                    .push(|idx| Building::new(variant, idx, world, position));
+            }
+        }
+    }
+
+    fn kill_enemies(&mut self) {
+        for enemy in &mut self.enemies {
+            if enemy.kill() {
+                let point = enemy.get_position(&self.world);
+                kill_enemy(
+                    &mut self.objective,
+                    &mut self.events,
+                    point,
+                    enemy.is_boss(),
+                );
+            }
+        }
+    }
+
+    /// Adds horses to the death ball, or lets go of animals in it, until it's the given size
+    fn set_death_ball_size(&mut self, size: u8) {
+        let world = &mut self.world;
+        let excess = self.death_ball_size.saturating_sub(size) as usize;
+        for animal in (&mut self.animals)
+            .into_iter()
+            .filter(|animal| animal.is_affected_by_death_ball)
+            .take(excess)
+        {
+            animal.is_affected_by_death_ball = false;
+        }
+
+        let position = self.death_ball.get_position(world);
+        for i in self.death_ball_size..size {
+            let offset = vec2((i % 8) as f32, (i / 8) as f32) * DEATH_BALL_SPACING;
+            let idx = self
+                .animals
+                .push(|idx| Animal::new(animals::Variant::Horse, idx, world, position + offset));
+            self.animals[idx].is_affected_by_death_ball = true;
+        }
+        self.update_death_ball_size();
+    }
+}
+
+/// Counts a kill towards the objective, and lets observers know
+fn kill_enemy(objective: &mut Objective, events: &mut Vec<Event>, point: Vec2, is_boss: bool) {
+    objective.on_kill_enemy(is_boss);
+    events.push(Event::Killed { point });
+    if is_boss {
+        events.push(Event::BossKilled);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(level: Level, source: &str) -> Simulation {
+        Simulation::new(level, &LevelDefinition::parse(source).unwrap(), Rng::new(0))
+    }
+
+    /// See `LevelDefinition::for_test`
+    fn level(objective: &str, entities: &str) -> Simulation {
+        let definition = LevelDefinition::for_test(objective, entities);
+        Simulation::new(Level::Test, &definition, Rng::new(0))
+    }
+
+    /// Steps the simulation with the commands returned by the script until the level is over or
+    /// the time limit (in simulated seconds) is reached
+    fn run(
+        sim: &mut Simulation,
+        time_limit: f32,
+        mut script: impl FnMut(&Simulation) -> Commands,
+    ) -> Status {
+        for _ in 0..(time_limit / DT) as u32 {
+            let commands = script(sim);
+            sim.step(&commands);
+            if matches!(sim.status, Status::HasWon | Status::HasLost) {
+                break;
+            }
+        }
+        sim.status
+    }
+
+    fn nearest(from: Vec2, positions: impl Iterator<Item = Vec2>) -> Option<Vec2> {
+        positions.min_by(|a, b| from.distance(*a).total_cmp(&from.distance(*b)))
+    }
+
+    /// Gathers up any loose animals, then repeatedly rams the nearest enemy, while keeping out of
+    /// fires
+    fn hunt_enemies() -> impl FnMut(&Simulation) -> Commands {
+        let mut steps = 0;
+        move |sim| {
+            steps += 1;
+
+            let world = &sim.world;
+            let position = sim.death_ball.get_position(world);
+            let loose = sim
+                .animals
+                .into_iter()
+                .filter(|animal| !animal.is_affected_by_death_ball)
+                .map(|animal| animal.get_position(world));
+            let enemies = sim
+                .enemies
+                .into_iter()
+                .map(|enemy| enemy.get_position(world));
+
+            let target = match nearest(position, loose) {
+                // animals are only picked up as the death ball moves over them, so step off first
+                Some(animal) if animal.distance(position) < 100. => position + vec2(200., 0.),
+                Some(animal) => animal,
+                // damage is only dealt as contact starts, so back off every so often
+                None => match nearest(position, enemies) {
+                    Some(enemy) if steps % 120 < 90 => enemy,
+                    Some(enemy) => enemy + vec2(0., 300.),
+                    None => position,
+                },
+            };
+            // hold off on dragging everything through fires
+            let fire = sim
+                .fires
+                .into_iter()
+                .find(|fire| fire.position.distance(position) < fire.radius * 2.);
+            let target = match fire {
+                Some(fire) => position + (position - fire.position).normalize_or_zero() * 300.,
+                None => target,
+            };
+            Commands {
+                move_deathball: Some(target),
+            }
+        }
+    }
+
+    #[test]
+    fn should_win_by_surviving_waves() {
+        let mut sim = level(
+            "kind: SurviveWaves, target: 2",
+            "spawners: [(position: (2000., 0.), waves: [ \
+                  (duration: 1., interval: 0.5, enemies: [(Farmer, 1)]), \
+                  (duration: 1., interval: 0.5, enemies: [(Farmer, 2)]), \
+              ])]",
+        );
+        let status = run(&mut sim, 5., |_| Commands::default());
+        assert_eq!(status, Status::HasWon);
+        assert_eq!(sim.waves_survived(), 2);
+        assert_eq!(sim.enemies.into_iter().count(), 3);
+        assert!((sim.time - 2.).abs() < 0.1);
+    }
+
+    #[test]
+    fn should_only_count_tagged_buildings() {
+        let mut sim = level(
+            "kind: DestroyTagged(\"Depot\")",
+            "buildings: [(OilBarrel, (-1000., 0.))], \
+              tagged_buildings: [(\"Depot\", OilBarrel, (1000., 0.))]",
+        );
+        let explode = |sim: &mut Simulation, x: f32| {
+            sim.world.spawned.push(Spawn::Explosion {
+                position: vec2(x, 0.),
+                radius: 100.,
+                impulse: 0.,
+                damage: 200,
+            });
+            sim.step(&Commands::default());
+            sim.step(&Commands::default());
+        };
+
+        explode(&mut sim, -1000.);
+        assert_eq!(sim.status, Status::Playing);
+        explode(&mut sim, 1000.);
+        assert_eq!(sim.status, Status::HasWon);
+    }
+
+    #[test]
+    fn should_lose_once_out_of_time() {
+        let mut sim = level(
+            "kind: KillEnemies, time_limit: Some(1.)",
+            "enemies: [(Farmer, (2000., 0.))]",
+        );
+        let status = run(&mut sim, 5., |_| Commands::default());
+        assert_eq!(status, Status::HasLost);
+        assert!((sim.time - 1.).abs() < 0.1);
+    }
+
+    #[test]
+    fn should_set_the_death_ball_size() {
+        let mut sim = level("kind: None", "");
+        run(&mut sim, 1., |_| Commands::default());
+        assert_eq!(sim.death_ball_size, 1);
+
+        sim.set_death_ball_size(12);
+        assert_eq!(sim.death_ball_size, 12);
+        sim.set_death_ball_size(3);
+        assert_eq!(sim.death_ball_size, 3);
+        assert_eq!(sim.animals.into_iter().count(), 12);
+    }
+
+    #[test]
+    fn should_count_killing_every_enemy_towards_the_objective() {
+        let mut sim = level(
+            "kind: KillEnemies, target: 3",
+            "enemies: [(Farmer, (2000., 0.)), (Police, (-2000., 0.))]",
+        );
+        sim.spawn(Spawnable::Enemy(enemies::Variant::Soldier), vec2(0., 2000.));
+        sim.kill_enemies();
+        sim.kill_enemies();
+        sim.step(&Commands::default());
+        assert_eq!(sim.objective.current(), 3);
+        assert_eq!(sim.status, Status::HasWon);
+    }
+
+    #[test]
+    fn should_stop_projectiles_at_buildings() {
+        let with_buildings = |buildings: &str| {
+            let entities = format!("animals: [(Horse, (300., 0.))], buildings: [{}]", buildings);
+            level("kind: KillEnemies, target: 1", &entities)
+        };
+        // returns the number of animals hit
+        let fire = |sim: &mut Simulation| {
+            sim.world.spawned.push(Spawn::Projectile {
+                position: vec2(100., 0.),
+                velocity: vec2(500., 0.),
+                impulse: 300.,
+                damage: 0,
+            });
+            let mut hits = 0;
+            for _ in 0..60 {
+                sim.step(&Commands::default());
+                hits += sim
+                    .drain_events()
+                    .filter(|event| matches!(event, Event::Hit { .. }))
+                    .count();
+            }
+            hits
+        };
+
+        let mut open = with_buildings("");
+        assert_eq!(fire(&mut open), 1);
+        assert!(open.projectiles.into_iter().next().is_none());
+
+        let mut blocked = with_buildings("(FenceV, (200., 0.))");
+        assert_eq!(fire(&mut blocked), 0);
+        assert!(blocked.projectiles.into_iter().next().is_none());
+    }
+
+    #[test]
+    fn should_remove_animals_once_killed() {
+        let mut sim = level(
+            "kind: SaveAnimals, target: 1",
+            "animals: [(Horse, (300., 0.))]",
+        );
+        sim.world.spawned.push(Spawn::Projectile {
+            position: vec2(100., 0.),
+            velocity: vec2(500., 0.),
+            impulse: 300.,
+            damage: 100,
+        });
+
+        let mut killed = 0;
+        for _ in 0..(2. / DT) as u32 {
+            sim.step(&Commands::default());
+            killed += sim
+                .drain_events()
+                .filter(|event| matches!(event, Event::AnimalKilled { .. }))
+                .count();
+        }
+        assert_eq!(killed, 1);
+        assert!(sim.animals.into_iter().next().is_none());
+    }
+
+    #[test]
+    fn should_break_destroyed_buildings_into_debris() {
+        let mut sim = level(
+            "kind: DestroyBuildings, target: 1",
+            "buildings: [(Barn, (500., 0.))]",
+        );
+        for building in &mut sim.buildings {
+            building.damage(u8::MAX);
+        }
+
+        sim.step(&Commands::default());
+        assert!(sim.buildings.into_iter().next().is_none());
+        assert!(sim.debris.into_iter().count() > 1);
+        // the barn's animals, along with the horse the death ball started with
+        assert_eq!(sim.animals.into_iter().count(), 3 + 1);
+
+        for _ in 0..(5. / DT) as u32 {
+            sim.step(&Commands::default());
+        }
+        assert!(sim.debris.into_iter().next().is_none());
+    }
+
+    #[test]
+    fn should_set_off_chain_reactions() {
+        let mut sim = level(
+            "kind: DestroyBuildings, target: 3",
+            "buildings: [(OilBarrel, (1000., 0.)), (OilBarrel, (1200., 0.)), \
+                          (Outhouse, (1400., 0.)), (Barn, (3000., 0.))]",
+        );
+        if let Some(barrel) = (&mut sim.buildings).into_iter().next() {
+            barrel.damage(u8::MAX);
+        }
+
+        let mut explosions = 0;
+        for _ in 0..10 {
+            sim.step(&Commands::default());
+            explosions += sim
+                .drain_events()
+                .filter(|event| matches!(event, Event::Exploded { .. }))
+                .count();
+        }
+        assert_eq!(explosions, 2);
+        // everything but the first barrel, which wasn't destroyed by an explosion
+        assert_eq!(sim.objective.current(), 2);
+        // the barn was out of range
+        assert_eq!(sim.buildings.into_iter().count(), 1);
+    }
+
+    #[test]
+    fn should_burn_animals_and_enemies_over_time() {
+        let mut sim = level(
+            "kind: KillEnemies, target: 1",
+            "enemies: [(Farmer, (-1000., 0.))]",
+        );
+        // alongside the one the death ball starts with
+        sim.spawn(Spawnable::Animal(animals::Variant::Horse), vec2(1000., 0.));
+        for x in [1000., -1000.] {
+            sim.world.spawned.push(Spawn::Fire {
+                position: vec2(x, 0.),
+                radius: 100.,
+                damage: 4,
+                duration: 10.,
+            });
+        }
+
+        let count_kills = |sim: &mut Simulation, seconds: f32| {
+            let (mut animals, mut enemies) = (0, 0);
+            for _ in 0..(seconds / DT) as u32 {
+                sim.step(&Commands::default());
+                for event in sim.drain_events() {
+                    match event {
+                        Event::AnimalKilled { .. } => animals += 1,
+                        Event::Killed { .. } => enemies += 1,
+                        _ => {}
+                    }
+                }
+            }
+            (animals, enemies)
+        };
+        assert_eq!(count_kills(&mut sim, 2.5), (0, 0), "not in one go");
+        // the farmer has 10 health and the horse 16, at 4 damage a second
+        assert_eq!(count_kills(&mut sim, 2.), (1, 1));
+        assert!(sim.objective.is_complete());
+    }
+
+    #[test]
+    fn should_open_gates_with_levers() {
+        let mut sim = level(
+            "kind: KillEnemies, target: 1",
+            "animals: [(Horse, (200., 0.))], \
+              buildings: [(Lever, (500., 0.)), (GateV, (800., 0.))]",
+        );
+        let mut commands = Commands {
+            move_deathball: Some(vec2(200., 0.)),
+        };
+        sim.step(&commands);
+        commands.move_deathball = Some(vec2(500., 0.));
+        for _ in 0..(3. / DT) as u32 {
+            sim.step(&commands);
+        }
+
+        assert_eq!(sim.buildings.into_iter().count(), 1);
+        assert!(!sim.buildings.into_iter().any(Building::is_gate));
+    }
+
+    #[test]
+    fn should_kill_poisoned_enemies_over_time() {
+        let mut sim = level(
+            "kind: KillEnemies, target: 1",
+            "enemies: [(Farmer, (1000., 0.))]",
+        );
+        for enemy in &mut sim.enemies {
+            let poison = Ability::Poison {
+                damage: 4.,
+                duration: 3.,
+            };
+            enemy.afflict(poison, Vec2::X);
+        }
+
+        let mut killed = 0;
+        for _ in 0..(4. / DT) as u32 {
+            sim.step(&Commands::default());
+            killed += sim
+                .drain_events()
+                .filter(|event| matches!(event, Event::Killed { .. }))
+                .count();
+        }
+        assert_eq!(killed, 1);
+        assert!(sim.objective.is_complete());
+    }
+
+    #[test]
+    fn should_lose_if_the_death_ball_never_picks_up_an_animal() {
+        let mut sim = load(
+            Level::Tutorial,
+            include_str!("../assets/levels/tutorial_scenario.ron"),
+        );
+        let status = run(&mut sim, LOSE_TIME + 1., |_| Commands {
+            move_deathball: Some(vec2(100_000., 100_000.)),
+        });
+        assert_eq!(status, Status::HasLost);
+    }
+
+    #[test]
+    fn should_count_down_the_score_while_playing() {
+        let mut sim = load(
+            Level::Scenario1,
+            include_str!("../assets/levels/scenario_1.ron"),
+        );
+        run(&mut sim, 1., |_| Commands::default());
+        assert_eq!(sim.status, Status::Playing);
+        assert!((sim.score - 29900.).abs() < 5.);
+    }
+
+    #[test]
+    fn should_play_out_the_same_way_given_the_same_seed() {
+        let play = || {
+            let mut sim = load(
+                Level::Scenario1,
+                include_str!("../assets/levels/scenario_1.ron"),
+            );
+            run(&mut sim, 60., hunt_enemies());
+            let positions: Vec<_> = sim
+                .animals
+                .into_iter()
+                .map(|animal| animal.get_position(&sim.world))
+                .collect();
+            (sim.objective.current(), positions)
+        };
+        assert_eq!(play(), play());
+    }
+
+    #[test]
+    fn should_be_able_to_win_scenario_1() {
+        let mut sim = load(
+            Level::Scenario1,
+            include_str!("../assets/levels/scenario_1.ron"),
+        );
+        let status = run(&mut sim, 600., hunt_enemies());
+        assert_eq!(status, Status::HasWon);
+        assert!(sim.score > 0.);
+    }
+}
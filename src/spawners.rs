@@ -0,0 +1,154 @@
+//! Invisible points that send enemies in over time, in waves, see `LevelDefinition::spawners`.
+
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    enemies::Variant as EnemyVariant,
+    simulation::{Spawn, World},
+};
+
+/// How far from the spawner's position enemies can appear
+const SPREAD: f32 = 48.;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Wave {
+    /// Time from the start of this wave until the next one starts
+    pub duration: f32,
+    /// Time between each enemy
+    pub interval: f32,
+    pub enemies: Vec<(EnemyVariant, u8)>,
+}
+
+pub struct Spawner {
+    position: Vec2,
+    waves: Vec<Wave>,
+    /// Once out of waves, keep repeating the last one with an extra of each enemy every time
+    is_endless: bool,
+    /// The number of waves that have run their full duration
+    waves_survived: u16,
+    timer: f32,
+    spawn_timer: f32,
+    /// Left to spawn this wave, in reverse order
+    queue: Vec<EnemyVariant>,
+}
+
+impl Spawner {
+    pub fn new(position: Vec2, waves: Vec<Wave>, is_endless: bool) -> Self {
+        let mut spawner = Spawner {
+            position,
+            waves,
+            is_endless,
+            waves_survived: 0,
+            timer: 0.,
+            spawn_timer: 0.,
+            queue: Vec::new(),
+        };
+        spawner.start_wave();
+        spawner
+    }
+
+    pub fn waves_survived(&self) -> u16 {
+        self.waves_survived
+    }
+
+    pub fn update(&mut self, world: &mut World) {
+        let wave = match self.current_wave() {
+            Some(wave) => wave,
+            None => return,
+        };
+        let (duration, interval) = (wave.duration, wave.interval);
+
+        self.spawn_timer -= world.delta;
+        if self.spawn_timer <= 0. {
+            if let Some(variant) = self.queue.pop() {
+                self.spawn_timer += interval;
+                let offset = vec2(
+                    world.rng.gen_range(-SPREAD, SPREAD),
+                    world.rng.gen_range(-SPREAD, SPREAD),
+                );
+                world
+                    .spawned
+                    .push(Spawn::Enemy(variant, self.position + offset));
+            }
+        }
+
+        self.timer += world.delta;
+        if self.timer >= duration {
+            self.waves_survived += 1;
+            self.start_wave();
+        }
+    }
+
+    fn current_wave(&self) -> Option<&Wave> {
+        let index = self.waves_survived as usize;
+        match self.waves.get(index) {
+            Some(wave) => Some(wave),
+            None if self.is_endless => self.waves.last(),
+            None => None,
+        }
+    }
+
+    fn start_wave(&mut self) {
+        self.timer = 0.;
+        self.spawn_timer = 0.;
+        self.queue.clear();
+        let extra = (self.waves_survived as usize + 1).saturating_sub(self.waves.len());
+        if let Some(wave) = self.current_wave() {
+            let queue =
+                wave.enemies.iter().rev().flat_map(|&(variant, count)| {
+                    std::iter::repeat_n(variant, count as usize + extra)
+                });
+            self.queue = queue.collect();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    fn world() -> World {
+        World {
+            delta: 0.125,
+            ..World::new(Rng::new(0))
+        }
+    }
+
+    fn wave(count: u8) -> Wave {
+        Wave {
+            duration: 1.,
+            interval: 0.25,
+            enemies: vec![(EnemyVariant::Farmer, count)],
+        }
+    }
+
+    #[test]
+    fn should_spawn_each_wave_over_time() {
+        let mut world = world();
+        let mut spawner = Spawner::new(Vec2::ZERO, vec![wave(2), wave(3)], false);
+        let mut spawned = Vec::new();
+        for _ in 0..50 {
+            spawner.update(&mut world);
+            spawned.push(world.spawned.drain(..).count());
+        }
+
+        assert_eq!(spawned[..8].iter().sum::<usize>(), 2);
+        assert_eq!(spawned[8..].iter().sum::<usize>(), 3);
+        assert_eq!(spawner.waves_survived(), 2);
+    }
+
+    #[test]
+    fn should_keep_growing_the_last_wave_when_endless() {
+        let mut world = world();
+        let mut spawner = Spawner::new(Vec2::ZERO, vec![wave(1)], true);
+        for _ in 0..24 {
+            spawner.update(&mut world);
+        }
+
+        // waves of 1, 2 then 3
+        assert_eq!(world.spawned.len(), 6);
+        assert_eq!(spawner.waves_survived(), 3);
+    }
+}
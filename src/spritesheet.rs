@@ -19,16 +19,21 @@ impl Spritesheet {
 
     pub fn multisprite(&self, position: Vec2, size: Vec2) -> Sprite {
         let size = self.cell_size * size;
+        self.region(Rect::new(
+            self.cell_size * position.x,
+            self.cell_size * position.y,
+            size.x,
+            size.y,
+        ))
+    }
+
+    /// Any part of the sheet, in pixels rather than cells
+    pub fn region(&self, source: Rect) -> Sprite {
         Sprite {
             sheet: *self,
-            size,
+            size: source.size(),
             scale: 1.,
-            source: Rect::new(
-                self.cell_size * position.x,
-                self.cell_size * position.y,
-                size.x,
-                size.y,
-            ),
+            source,
         }
     }
 }
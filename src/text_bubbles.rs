@@ -9,15 +9,13 @@ const FONT_SIZE: u16 = 16;
 const BG_COLOR: Color = Color::new(0., 0., 0., 0.733);
 const FG_COLOR: Color = WHITE;
 
-type Rows = &'static [&'static str];
-
 pub struct TextBubble {
     position: Vec2,
-    rows: Rows,
+    rows: Vec<String>,
 }
 
 impl TextBubble {
-    pub fn new(position: Vec2, rows: Rows) -> Self {
+    pub fn new(position: Vec2, rows: Vec<String>) -> Self {
         TextBubble { position, rows }
     }
 
@@ -0,0 +1,99 @@
+//! How fast time passes in levels, compared to real time.
+//!
+//! Everything in a level runs off the simulation's fixed steps, so scaling the time fed into it
+//! speeds up or slows down every timer (attack cooldowns, fades, the score countdown) together.
+
+use crate::simulation::{DT, MAX_STEPS};
+
+const FAST_FORWARD_SCALE: f32 = 3.;
+const SLOW_MOTION_SCALE: f32 = 0.25;
+
+pub struct TimeScale {
+    /// Applies on top of everything else, set from the debug window
+    pub base: f32,
+    pub is_fast_forward: bool,
+    /// Only advance by the steps queued up with `step`, rather than by time
+    pub is_stepping: bool,
+    /// In real seconds, overrides fast forward
+    slow_motion: f32,
+    steps: u8,
+}
+
+impl TimeScale {
+    pub fn new() -> Self {
+        TimeScale {
+            base: 1.,
+            is_fast_forward: false,
+            is_stepping: false,
+            slow_motion: 0.,
+            steps: 0,
+        }
+    }
+
+    pub fn scale(&self) -> f32 {
+        if self.slow_motion > 0. {
+            self.base * SLOW_MOTION_SCALE
+        } else if self.is_fast_forward {
+            self.base * FAST_FORWARD_SCALE
+        } else {
+            self.base
+        }
+    }
+
+    /// Makes sure slow motion lasts at least this long, in real seconds
+    pub fn slow_motion(&mut self, duration: f32) {
+        self.slow_motion = self.slow_motion.max(duration);
+    }
+
+    pub fn step(&mut self) {
+        self.steps = self.steps.saturating_add(1);
+    }
+
+    /// Takes the steps queued since the last time this was called
+    pub fn take_steps(&mut self) -> u8 {
+        std::mem::take(&mut self.steps)
+    }
+
+    /// Counts down slow motion, returns how much time passes in the level this frame, at most
+    /// what the simulation can step through in one update
+    pub fn update(&mut self, delta: f32) -> f32 {
+        let scaled = (delta * self.scale()).min(MAX_STEPS as f32 * DT);
+        self.slow_motion = (self.slow_motion - delta).max(0.);
+        scaled
+    }
+
+    /// For starting a level, keeping the debug settings
+    pub fn reset(&mut self) {
+        self.is_fast_forward = false;
+        self.slow_motion = 0.;
+        self.steps = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_slow_down_fast_forward_for_slow_motion() {
+        let mut time_scale = TimeScale::new();
+        time_scale.is_fast_forward = true;
+        assert_eq!(time_scale.update(0.0625), 0.1875);
+
+        time_scale.slow_motion(0.125);
+        time_scale.slow_motion(0.0625);
+        assert_eq!(time_scale.update(0.0625), 0.015625);
+        assert_eq!(time_scale.update(0.0625), 0.015625);
+        assert_eq!(time_scale.update(0.0625), 0.1875);
+    }
+
+    #[test]
+    fn should_only_pass_as_much_time_as_the_simulation_can_step() {
+        let mut time_scale = TimeScale::new();
+        time_scale.base = 4.;
+        time_scale.is_fast_forward = true;
+        let max = MAX_STEPS as f32 * DT;
+        assert_eq!(time_scale.update(DT), max, "12x at 60 FPS");
+        assert_eq!(time_scale.update(1.), max);
+    }
+}